@@ -12,6 +12,8 @@ pub enum InstructionType {
     Intercross,
     Concat,
     Constant,
+    Lambda,   // data is the parameter names joined by ',', params is the body
+    Variable, // a parameter of an enclosing lambda, data is its name
 }
 
 #[derive(Debug)]
@@ -21,6 +23,7 @@ pub struct Instruction {
     pub semantic_type: SemanticType,
     pub data: Option<String>,
     pub params: Option<Vec<Box<Instruction>>>,
+    pub named_params: Vec<(String, String)>, // constants only, `ns="틀"`
 }
 
 impl Instruction {
@@ -48,8 +51,18 @@ impl Instruction {
                 .map(|x| match x.inst_type {
                     InstructionType::UseFunction => format!("ref(\"{}\")", x.data.clone().unwrap()),
                     InstructionType::Constant => format!("\"{}\"", x.data.clone().unwrap()),
+                    InstructionType::Lambda => format!(
+                        "({}) => v{}",
+                        x.data.clone().unwrap(),
+                        x.params.as_ref().unwrap()[0].id
+                    ),
                     _ => format!("v{}", x.id),
                 })
+                .chain(
+                    self.named_params
+                        .iter()
+                        .map(|(name, value)| format!("{}=\"{}\"", name, value)),
+                )
                 .collect::<Vec<String>>()
                 .join(", ")
         }
@@ -95,6 +108,8 @@ impl IRBuilder {
                     .filter(|x| match x.inst_type {
                         InstructionType::UseFunction => false,
                         InstructionType::Constant => false,
+                        // evaluated once per call, see `ir_flatten_lambda`
+                        InstructionType::Lambda => false,
                         _ => true,
                     })
                     .map(|x| &*x.as_ref())
@@ -105,6 +120,11 @@ impl IRBuilder {
         insts
     }
 
+    // Instructions of the body of a lambda in evaluation order.
+    pub fn ir_flatten_lambda(lambda: &Instruction) -> Vec<&Instruction> {
+        Self::ir_flatten(&lambda.params.as_ref().unwrap()[0])
+    }
+
     fn visit_root(id_count: &mut usize, node: CommandExpressionNode) -> Instruction {
         Self::visit_expr_and(id_count, *node.expr_and)
     }
@@ -132,6 +152,7 @@ impl IRBuilder {
             semantic_type: semantic_type,
             data: None,
             params: Some(params),
+            named_params: Vec::new(),
         }
     }
 
@@ -158,6 +179,7 @@ impl IRBuilder {
             semantic_type: semantic_type,
             data: None,
             params: Some(params),
+            named_params: Vec::new(),
         }
     }
 
@@ -172,7 +194,32 @@ impl IRBuilder {
     fn visit_func(id_count: &mut usize, node: FunctionExpressionNode) -> Instruction {
         let semantic_type = node.semantic_type.unwrap();
 
+        if node.is_variable {
+            *id_count += 1;
+
+            return Instruction {
+                id: *id_count,
+                inst_type: InstructionType::Variable,
+                semantic_type,
+                data: Some(node.name),
+                params: None,
+                named_params: Vec::new(),
+            };
+        }
+
         if node.is_use {
+            // constants of predicates, `has_category("게임")`
+            let params = if node.args.is_empty() {
+                None
+            } else {
+                Some(
+                    node.args
+                        .into_iter()
+                        .map(|x| Box::new(Self::visit_arg(id_count, *x)))
+                        .collect(),
+                )
+            };
+
             *id_count += 1;
 
             Instruction {
@@ -180,28 +227,15 @@ impl IRBuilder {
                 inst_type: InstructionType::UseFunction,
                 semantic_type: semantic_type,
                 data: Some(node.name),
-                params: None,
+                params,
+                named_params: Vec::new(),
             }
         } else {
-            let mut params: Vec<Box<Instruction>> = Vec::new();
-
-            for arg in node.args {
-                let inst = if let Some(e) = arg.value {
-                    *id_count += 1;
-
-                    Instruction {
-                        id: *id_count,
-                        inst_type: InstructionType::Constant,
-                        semantic_type: arg.semantic_type.unwrap(),
-                        data: Some(e),
-                        params: None,
-                    }
-                } else {
-                    Self::visit_expr_and(id_count, *arg.expr_and.unwrap())
-                };
-
-                params.push(Box::new(inst));
-            }
+            let params = node
+                .args
+                .into_iter()
+                .map(|x| Box::new(Self::visit_arg(id_count, *x)))
+                .collect();
 
             *id_count += 1;
 
@@ -211,9 +245,46 @@ impl IRBuilder {
                 semantic_type,
                 data: Some(node.name),
                 params: Some(params),
+                named_params: node
+                    .named_args
+                    .into_iter()
+                    .map(|(name, arg)| (name, arg.value.unwrap()))
+                    .collect(),
             }
         }
     }
+
+    fn visit_arg(id_count: &mut usize, arg: ArgumentNode) -> Instruction {
+        if let Some(lambda) = arg.lambda {
+            let body = Self::visit_expr_and(id_count, *lambda.body);
+
+            *id_count += 1;
+
+            return Instruction {
+                id: *id_count,
+                inst_type: InstructionType::Lambda,
+                semantic_type: arg.semantic_type.unwrap(),
+                data: Some(lambda.params.join(",")),
+                params: Some(vec![Box::new(body)]),
+                named_params: Vec::new(),
+            };
+        }
+
+        if let Some(e) = arg.value {
+            *id_count += 1;
+
+            Instruction {
+                id: *id_count,
+                inst_type: InstructionType::Constant,
+                semantic_type: arg.semantic_type.unwrap(),
+                data: Some(e),
+                params: None,
+                named_params: Vec::new(),
+            }
+        } else {
+            Self::visit_expr_and(id_count, *arg.expr_and.unwrap())
+        }
+    }
 }
 
 #[cfg(test)]
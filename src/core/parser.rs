@@ -48,18 +48,34 @@ pub struct ExpressionCaseNode {
 #[derive(Debug)]
 pub struct FunctionExpressionNode {
     pub name: String,
-    pub is_use: bool, // A function is used as an argument to another function.
+    pub is_use: bool,      // A function is used as an argument to another function.
+    pub is_variable: bool, // A parameter of an enclosing lambda, bound by the semantic analysis.
     pub args: Vec<Box<ArgumentNode>>,
+    pub named_args: Vec<NamedArgumentNode>, // after the other arguments
     pub semantic_type: Option<SemanticType>,
 }
 
+// `ns="틀"`
+pub type NamedArgumentNode = (String, Box<ArgumentNode>);
+
 #[derive(Debug)]
 pub struct ArgumentNode {
     pub value: Option<String>,
     pub expr_and: Option<Box<ExpressionAndNode>>,
+    pub lambda: Option<Box<LambdaNode>>,
     pub semantic_type: Option<SemanticType>,
 }
 
+// `x => count(category(x))`, `(a, b) => cmp_array(a, b)`
+#[derive(Debug)]
+pub struct LambdaNode {
+    pub params: Vec<String>,
+    pub body: Box<ExpressionAndNode>,
+}
+
+// positional and named arguments
+type ParsedArguments = (Vec<Box<ArgumentNode>>, Vec<NamedArgumentNode>);
+
 impl Parser {
     pub fn from(target: &str) -> Parser {
         Parser {
@@ -154,7 +170,9 @@ impl Parser {
             return Ok(Box::new(FunctionExpressionNode {
                 name: name.content.unwrap(),
                 is_use: true,
+                is_variable: false,
                 args: Vec::new(),
+                named_args: Vec::new(),
                 semantic_type: None,
             }));
         }
@@ -169,12 +187,14 @@ impl Parser {
             return Ok(Box::new(FunctionExpressionNode {
                 name: name.content.unwrap(),
                 is_use: false,
+                is_variable: false,
                 args: Vec::new(),
+                named_args: Vec::new(),
                 semantic_type: None,
             }));
         }
 
-        let args = self.parse_args()?;
+        let (args, named_args) = self.parse_args()?;
 
         if self.tokenizer.lookup() != TokenType::BraceEnd {
             return Err("expect )".into());
@@ -186,23 +206,41 @@ impl Parser {
         Ok(Box::new(FunctionExpressionNode {
             name: name.content.unwrap(),
             is_use: false,
+            is_variable: false,
             args: args,
+            named_args,
             semantic_type: None,
         }))
     }
 
-    fn parse_args(&mut self) -> Result<Vec<Box<ArgumentNode>>, Box<dyn Error>> {
+    fn parse_args(&mut self) -> Result<ParsedArguments, Box<dyn Error>> {
         let mut args: Vec<Box<ArgumentNode>> = Vec::new();
+        let mut named_args: Vec<NamedArgumentNode> = Vec::new();
+
+        loop {
+            if self.tokenizer.lookup() == TokenType::Name
+                && self.tokenizer.lookup_nth(2) == TokenType::Equal
+            {
+                // consume name and =
+                let name = self.tokenizer.next().content.unwrap();
+                self.tokenizer.next();
+
+                named_args.push((name, self.parse_arg()?));
+            } else if named_args.is_empty() {
+                args.push(self.parse_arg()?);
+            } else {
+                return Err("expect named argument after named argument".into());
+            }
 
-        args.push(self.parse_arg()?);
+            if self.tokenizer.lookup() != TokenType::Comma {
+                break;
+            }
 
-        while self.tokenizer.lookup() == TokenType::Comma {
             // comsume ,
             self.tokenizer.next();
-            args.push(self.parse_arg()?);
         }
 
-        Ok(args)
+        Ok((args, named_args))
     }
 
     fn parse_arg(&mut self) -> Result<Box<ArgumentNode>, Box<dyn Error>> {
@@ -210,32 +248,93 @@ impl Parser {
             // consume const
             let co = self.tokenizer.next();
 
-            if self.tokenizer.lookup() != TokenType::Comma {
-                return Ok(Box::new(ArgumentNode {
-                    value: Some(co.content.unwrap()),
-                    expr_and: None,
-                    semantic_type: Some(SemanticType::Primitive(SemanticPrimitiveType::String)),
-                }));
-            }
-
-            // consume ,
-            self.tokenizer.next();
-
             return Ok(Box::new(ArgumentNode {
                 value: Some(co.content.unwrap()),
                 expr_and: None,
+                lambda: None,
                 semantic_type: Some(SemanticType::Primitive(SemanticPrimitiveType::String)),
             }));
         }
 
+        if let Some(params) = self.lookup_lambda_params() {
+            return Ok(Box::new(ArgumentNode {
+                value: None,
+                expr_and: None,
+                lambda: Some(self.parse_lambda(params)?),
+                semantic_type: None,
+            }));
+        }
+
         let expr_and = self.parse_expr_and()?;
 
         return Ok(Box::new(ArgumentNode {
             value: None,
             expr_and: Some(expr_and),
+            lambda: None,
             semantic_type: None,
         }));
     }
+
+    // Number of parameters if a lambda follows, `x =>` or `(a, b) =>`.
+    fn lookup_lambda_params(&mut self) -> Option<usize> {
+        if self.tokenizer.lookup() == TokenType::Name {
+            return (self.tokenizer.lookup_nth(2) == TokenType::Arrow).then_some(1);
+        }
+
+        if self.tokenizer.lookup() != TokenType::BraceStart {
+            return None;
+        }
+
+        let mut params = 0;
+
+        loop {
+            if self.tokenizer.lookup_nth(params * 2 + 2) != TokenType::Name {
+                return None;
+            }
+
+            params += 1;
+
+            match self.tokenizer.lookup_nth(params * 2 + 1) {
+                TokenType::Comma => {}
+                TokenType::BraceEnd => break,
+                _ => return None,
+            }
+        }
+
+        (self.tokenizer.lookup_nth(params * 2 + 2) == TokenType::Arrow).then_some(params)
+    }
+
+    fn parse_lambda(&mut self, params: usize) -> Result<Box<LambdaNode>, Box<dyn Error>> {
+        let braced = self.tokenizer.lookup() == TokenType::BraceStart;
+        let mut names: Vec<String> = Vec::new();
+
+        if braced {
+            // consume (
+            self.tokenizer.next();
+        }
+
+        for i in 0..params {
+            if i != 0 {
+                // consume ,
+                self.tokenizer.next();
+            }
+
+            names.push(self.tokenizer.next().content.unwrap());
+        }
+
+        if braced {
+            // consume )
+            self.tokenizer.next();
+        }
+
+        // consume =>
+        self.tokenizer.next();
+
+        Ok(Box::new(LambdaNode {
+            params: names,
+            body: self.parse_expr_and()?,
+        }))
+    }
 }
 
 #[cfg(test)]
@@ -276,4 +375,69 @@ mod tests {
         let mut p = Parser::from("map(reduce(title:startswith(\"서든\") | title:endswith(\"어택\"), category), select_max_len)");
         p.parse().unwrap();
     }
+
+    #[test]
+    fn parse_const_args_test() {
+        let mut p = Parser::from("title:fuzzy(\"동방\", 2)");
+        let root = p.parse().unwrap();
+
+        let args = &root.expr_and.expr_ors[0].expr_cases[0]
+            .func
+            .as_ref()
+            .unwrap()
+            .args;
+
+        assert_eq!(args.len(), 2);
+        assert_eq!(args[0].value.as_ref().unwrap(), "동방");
+        assert_eq!(args[1].value.as_ref().unwrap(), "2");
+    }
+
+    #[test]
+    fn parse_named_args_test() {
+        let mut p = Parser::from("title:contains(\"동방\", ns=\"틀\")");
+        let root = p.parse().unwrap();
+
+        let func = root.expr_and.expr_ors[0].expr_cases[0]
+            .func
+            .as_ref()
+            .unwrap();
+
+        assert_eq!(func.args.len(), 1);
+        assert_eq!(func.named_args.len(), 1);
+        assert_eq!(func.named_args[0].0, "ns");
+        assert_eq!(func.named_args[0].1.value.as_ref().unwrap(), "틀");
+
+        assert!(Parser::from("title:contains(ns=\"틀\", \"동방\")")
+            .parse()
+            .is_err());
+    }
+
+    #[test]
+    fn parse_lambda_test() {
+        let mut p = Parser::from("map(title:contains(\"동방\"), x => count(category(x)))");
+        let root = p.parse().unwrap();
+
+        let args = &root.expr_and.expr_ors[0].expr_cases[0]
+            .func
+            .as_ref()
+            .unwrap()
+            .args;
+
+        assert_eq!(args[1].lambda.as_ref().unwrap().params, vec!["x"]);
+
+        let mut p = Parser::from("sort(title:contains(\"동방\"), (a, b) => cmp_array(a, b))");
+        let root = p.parse().unwrap();
+
+        let args = &root.expr_and.expr_ors[0].expr_cases[0]
+            .func
+            .as_ref()
+            .unwrap()
+            .args;
+
+        assert_eq!(args[1].lambda.as_ref().unwrap().params, vec!["a", "b"]);
+
+        // a braced expression is not a lambda
+        let mut p = Parser::from("count((title:contains(\"동방\")))");
+        assert!(p.parse().is_ok());
+    }
 }
@@ -43,6 +43,9 @@ impl<'a> Printer<'a> {
                 RuntimeVariableAbstractPrimitiveData::String(e) => {
                     out.push_str(e);
                 }
+                RuntimeVariableAbstractPrimitiveData::Boolean(e) => {
+                    out.push_str(&e.to_string()[..]);
+                }
             },
             RuntimeVariableAbstractData::Array(e) => {
                 out.push_str(indent);
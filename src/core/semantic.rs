@@ -1,8 +1,10 @@
 use std::error::Error;
 
+use crate::model::namespace::Namespace;
+
 use super::parser::{
     ArgumentNode, CommandExpressionNode, ExpressionAndNode, ExpressionCaseNode, ExpressionOrNode,
-    FunctionExpressionNode,
+    FunctionExpressionNode, LambdaNode,
 };
 
 #[derive(PartialEq, Clone, Debug)]
@@ -20,11 +22,15 @@ pub enum SemanticFunctionType {
     Category,
     Select,
     Redirect,
+    Link,
+    Predicate,
+    Lambda,
     UnwrapTuple1,
     UnwrapTuple2,
     CmpArray,
     CmpTuple1,
     CmpTuple2,
+    Bind(Vec<SemanticFunctionType>),
 }
 
 #[derive(Clone, Debug)]
@@ -40,10 +46,8 @@ pub enum SemanticType {
 impl SemanticType {
     fn eq(&self, other: &SemanticType) -> bool {
         match self {
-            SemanticType::None => match other {
-                SemanticType::Primitive(_) | SemanticType::None => true,
-                _ => false,
-            },
+            // generic, any element type
+            SemanticType::None => !matches!(other, SemanticType::Function(_)),
             SemanticType::Primitive(e) => match other {
                 SemanticType::Primitive(o) => e == o,
                 SemanticType::None => true,
@@ -51,16 +55,19 @@ impl SemanticType {
             },
             SemanticType::Array(e) => match other {
                 SemanticType::Array(o) => e.eq(o),
+                SemanticType::None => true,
                 _ => false,
             },
             SemanticType::Set(e) => match other {
                 SemanticType::Set(o) => e.eq(o),
+                SemanticType::None => true,
                 _ => false,
             },
             SemanticType::Tuple(e) => match other {
                 SemanticType::Tuple(o) => {
                     e.len() == o.len() && e.iter().zip(o).all(|(x, y)| x.eq(y))
                 }
+                SemanticType::None => true,
                 _ => false,
             },
             SemanticType::Function(_) => unreachable!(),
@@ -73,10 +80,13 @@ impl SemanticType {
             SemanticType::Primitive(e) => match other {
                 SemanticType::None => Ok(Self::None),
                 SemanticType::Primitive(p) => {
-                    if e == p {
+                    if e != p {
+                        Err(format!("Types {:?} and {:?} do not match! The two elements have different types and cannot be merged.", e, p).into())
+                    } else if *e == SemanticPrimitiveType::Boolean {
+                        // `|` between booleans is a logical or
                         Ok(self.clone())
                     } else {
-                        Err(format!("Types {:?} and {:?} do not match! The two elements have different types and cannot be merged.", e, p).into())
+                        Ok(Self::Array(Box::new(self.clone())))
                     }
                 }
                 SemanticType::Array(o) => match o.as_ref() {
@@ -142,6 +152,13 @@ impl SemanticType {
     fn infer_intercross(&self, other: &SemanticType) -> Result<SemanticType, Box<dyn Error>> {
         match self {
             SemanticType::None => Ok(Self::None),
+            // `&` between booleans is a logical and
+            SemanticType::Primitive(SemanticPrimitiveType::Boolean) => match other {
+                SemanticType::None | SemanticType::Primitive(SemanticPrimitiveType::Boolean) => {
+                    Ok(self.clone())
+                }
+                _ => Err(format!("Types {:?} and {:?} do not match! A boolean can only be intercrossed with a boolean.", self, other).into()),
+            },
             SemanticType::Primitive(_) => {
                 Err("An intercrossing subject cannot be a primitive type.".into())
             }
@@ -170,14 +187,34 @@ impl SemanticType {
 }
 
 pub fn check_semantic(root: &mut CommandExpressionNode) -> Result<SemanticType, Box<dyn Error>> {
-    visit_expr_and(&mut root.expr_and)
+    let return_type = visit_expr_and(&mut root.expr_and)?;
+
+    check_not_function(&return_type)?;
+
+    Ok(return_type)
+}
+
+// Functions are values only as arguments of map, filter, sort, sort_by and bind.
+fn check_not_function(semantic_type: &SemanticType) -> Result<(), Box<dyn Error>> {
+    match semantic_type {
+        SemanticType::Function(_) => Err(
+            "A function without its subject can only be an argument of map, filter, sort, sort_by or bind."
+                .into(),
+        ),
+        _ => Ok(()),
+    }
 }
 
 fn visit_expr_and(node: &mut ExpressionAndNode) -> Result<SemanticType, Box<dyn Error>> {
     let mut return_type = visit_expr_or(&mut node.expr_ors[0])?;
 
+    if node.expr_ors.len() > 1 {
+        check_not_function(&return_type)?;
+    }
+
     for expr_or in &mut node.expr_ors.iter_mut().skip(1) {
         let target_type = visit_expr_or(expr_or)?;
+        check_not_function(&target_type)?;
         return_type = return_type.infer_intercross(&target_type)?;
     }
 
@@ -189,8 +226,13 @@ fn visit_expr_and(node: &mut ExpressionAndNode) -> Result<SemanticType, Box<dyn
 fn visit_expr_or(node: &mut ExpressionOrNode) -> Result<SemanticType, Box<dyn Error>> {
     let mut return_type = visit_expr_case(&mut node.expr_cases[0])?;
 
+    if node.expr_cases.len() > 1 {
+        check_not_function(&return_type)?;
+    }
+
     for expr_case in &mut node.expr_cases.iter_mut().skip(1) {
         let target_type = visit_expr_case(expr_case)?;
+        check_not_function(&target_type)?;
         return_type = return_type.infer_concat(&target_type)?;
     }
 
@@ -210,9 +252,11 @@ fn visit_expr_case(node: &mut ExpressionCaseNode) -> Result<SemanticType, Box<dy
         return result;
     }
 
-    let result = visit_func(node.func.as_mut().unwrap());
+    let func = node.func.as_mut().unwrap();
+    let result = visit_func(func);
 
     if let Ok(e) = &result {
+        check_named_args(func, e)?;
         node.semantic_type = Some(e.clone());
     }
 
@@ -220,12 +264,78 @@ fn visit_expr_case(node: &mut ExpressionCaseNode) -> Result<SemanticType, Box<dy
 }
 
 fn visit_func(node: &mut FunctionExpressionNode) -> Result<SemanticType, Box<dyn Error>> {
+    if node.is_variable {
+        return Ok(node.semantic_type.clone().unwrap());
+    }
+
+    // predicates taking constants, `has_category("게임")`, are used like `is_redirect`
+    if node.args.first().is_some_and(|x| x.value.is_some()) && is_predicate(&node.name) {
+        node.is_use = true;
+    }
+
+    // `bind(redirect, category)` composes use functions into one
+    if node.name == "bind" {
+        node.is_use = true;
+    }
+
     if node.is_use {
         return visit_func_use(node);
     }
 
     let result = match &node.name[..] {
-        "title:exact" | "title:contains" | "title:startswith" | "title:endswith" => {
+        "title:exact" | "title:contains" | "title:startswith" | "title:endswith"
+        | "title:chosung" | "title:jamo_prefix" => {
+            param_check_lazy_1(
+                node,
+                &SemanticType::Primitive(SemanticPrimitiveType::String),
+            )?;
+
+            Ok(SemanticType::Array(Box::new(SemanticType::Primitive(
+                SemanticPrimitiveType::Article,
+            ))))
+        }
+        "body:contains" | "body:regex" | "body:menu_exists" => {
+            param_check_lazy_1(
+                node,
+                &SemanticType::Primitive(SemanticPrimitiveType::String),
+            )?;
+
+            Ok(SemanticType::Array(Box::new(SemanticType::Primitive(
+                SemanticPrimitiveType::Article,
+            ))))
+        }
+        "links_to" | "redirects_to" => {
+            param_check_lazy_1(
+                node,
+                &SemanticType::Primitive(SemanticPrimitiveType::String),
+            )?;
+
+            Ok(SemanticType::Array(Box::new(SemanticType::Primitive(
+                SemanticPrimitiveType::Article,
+            ))))
+        }
+        "path" => {
+            // the depth limit is optional
+            if node.args.len() == 3 {
+                param_check_lazy_3(
+                    node,
+                    &SemanticType::Primitive(SemanticPrimitiveType::String),
+                    &SemanticType::Primitive(SemanticPrimitiveType::String),
+                    &SemanticType::Primitive(SemanticPrimitiveType::Integer),
+                )?;
+            } else {
+                param_check_lazy_2(
+                    node,
+                    &SemanticType::Primitive(SemanticPrimitiveType::String),
+                    &SemanticType::Primitive(SemanticPrimitiveType::String),
+                )?;
+            }
+
+            Ok(SemanticType::Array(Box::new(SemanticType::Primitive(
+                SemanticPrimitiveType::Article,
+            ))))
+        }
+        "category:exact" | "category:startswith" | "category:contains" => {
             param_check_lazy_1(
                 node,
                 &SemanticType::Primitive(SemanticPrimitiveType::String),
@@ -235,6 +345,82 @@ fn visit_func(node: &mut FunctionExpressionNode) -> Result<SemanticType, Box<dyn
                 SemanticPrimitiveType::Article,
             ))))
         }
+        "category:descendants" => {
+            // the depth limit is optional
+            if node.args.len() == 2 {
+                param_check_lazy_2(
+                    node,
+                    &SemanticType::Primitive(SemanticPrimitiveType::String),
+                    &SemanticType::Primitive(SemanticPrimitiveType::Integer),
+                )?;
+            } else {
+                param_check_lazy_1(
+                    node,
+                    &SemanticType::Primitive(SemanticPrimitiveType::String),
+                )?;
+            }
+
+            Ok(SemanticType::Array(Box::new(SemanticType::Primitive(
+                SemanticPrimitiveType::Article,
+            ))))
+        }
+        "ns:document" | "ns:template" | "ns:category" | "ns:file" | "ns:user" | "ns:wiki" => {
+            param_check_lazy_1(
+                node,
+                &SemanticType::Array(Box::new(SemanticType::Primitive(
+                    SemanticPrimitiveType::Article,
+                ))),
+            )?;
+
+            Ok(SemanticType::Array(Box::new(SemanticType::Primitive(
+                SemanticPrimitiveType::Article,
+            ))))
+        }
+        "rank" => {
+            param_check_lazy_1(
+                node,
+                &SemanticType::Array(Box::new(SemanticType::Primitive(
+                    SemanticPrimitiveType::Article,
+                ))),
+            )?;
+
+            Ok(SemanticType::Array(Box::new(SemanticType::Tuple(vec![
+                Box::new(SemanticType::Primitive(SemanticPrimitiveType::Article)),
+                Box::new(SemanticType::Primitive(SemanticPrimitiveType::Integer)),
+            ]))))
+        }
+        "body:search" => {
+            // the result size is optional
+            if node.args.len() == 2 {
+                param_check_lazy_2(
+                    node,
+                    &SemanticType::Primitive(SemanticPrimitiveType::String),
+                    &SemanticType::Primitive(SemanticPrimitiveType::Integer),
+                )?;
+            } else {
+                param_check_lazy_1(
+                    node,
+                    &SemanticType::Primitive(SemanticPrimitiveType::String),
+                )?;
+            }
+
+            Ok(SemanticType::Array(Box::new(SemanticType::Tuple(vec![
+                Box::new(SemanticType::Primitive(SemanticPrimitiveType::Article)),
+                Box::new(SemanticType::Primitive(SemanticPrimitiveType::Integer)),
+            ]))))
+        }
+        "title:fuzzy" => {
+            param_check_lazy_2(
+                node,
+                &SemanticType::Primitive(SemanticPrimitiveType::String),
+                &SemanticType::Primitive(SemanticPrimitiveType::Integer),
+            )?;
+
+            Ok(SemanticType::Array(Box::new(SemanticType::Tuple(vec![
+                Box::new(SemanticType::Primitive(SemanticPrimitiveType::Article)),
+                Box::new(SemanticType::Primitive(SemanticPrimitiveType::Integer)),
+            ]))))
+        }
         "count" => {
             let check_is_array =
                 param_check_lazy_1(node, &SemanticType::Array(Box::new(SemanticType::None)));
@@ -301,17 +487,9 @@ fn visit_func(node: &mut FunctionExpressionNode) -> Result<SemanticType, Box<dyn
                 _ => panic!("unreachable"),
             };
 
-            let mut second_param_func = get_second_param_func(node);
-
-            let semantic_type = visit_func_use(&mut second_param_func)?;
-            let func_type = match semantic_type {
-                SemanticType::Function(e) => e,
-                _ => unreachable!(),
-            };
-
-            Ok(SemanticType::Array(Box::new(visit_infer_func_use(
-                &func_type,
-                Some(&first_param_uncapsuled),
+            Ok(SemanticType::Array(Box::new(visit_second_param_func(
+                node,
+                &first_param_uncapsuled,
                 None,
             )?)))
         }
@@ -329,8 +507,7 @@ fn visit_func(node: &mut FunctionExpressionNode) -> Result<SemanticType, Box<dyn
 
             Ok(*first_param_uncapsuled)
         }
-        "filter" => todo!(),
-        "sort" => {
+        "filter" => {
             param_check_lazy_2(
                 node,
                 &SemanticType::Array(Box::new(SemanticType::None)),
@@ -343,17 +520,30 @@ fn visit_func(node: &mut FunctionExpressionNode) -> Result<SemanticType, Box<dyn
                 _ => panic!("unreachable"),
             };
 
-            let mut second_param_func = get_second_param_func(node);
+            let func_ret_type = visit_second_param_func(node, &first_param_uncapsuled, None)?;
+
+            if !func_ret_type.eq(&SemanticType::Primitive(SemanticPrimitiveType::Boolean)) {
+                return Err(format!("The second parameter of filter function must return Boolean! Currently, {:?} is returned!", func_ret_type).into());
+            }
 
-            let semantic_type = visit_func_use(&mut second_param_func)?;
-            let func_type = match semantic_type {
-                SemanticType::Function(e) => e,
-                _ => unreachable!(),
+            Ok(first_param_type)
+        }
+        "sort" | "sort:asc" | "sort:desc" => {
+            param_check_lazy_2(
+                node,
+                &SemanticType::Array(Box::new(SemanticType::None)),
+                &SemanticType::Function(SemanticFunctionType::None),
+            )?;
+
+            let first_param_type = visit_expr_and(node.args[0].expr_and.as_mut().unwrap())?;
+            let first_param_uncapsuled = match &first_param_type {
+                SemanticType::Array(e) => e.clone(),
+                _ => panic!("unreachable"),
             };
 
-            let func_ret_type = visit_infer_func_use(
-                &func_type,
-                Some(&first_param_uncapsuled),
+            let func_ret_type = visit_second_param_func(
+                node,
+                &first_param_uncapsuled,
                 Some(&first_param_uncapsuled),
             )?;
 
@@ -363,7 +553,29 @@ fn visit_func(node: &mut FunctionExpressionNode) -> Result<SemanticType, Box<dyn
 
             Ok(first_param_type)
         }
-        "bind" => todo!(),
+        "sort_by" | "sort_by:asc" | "sort_by:desc" => {
+            param_check_lazy_2(
+                node,
+                &SemanticType::Array(Box::new(SemanticType::None)),
+                &SemanticType::Function(SemanticFunctionType::None),
+            )?;
+
+            let first_param_type = visit_expr_and(node.args[0].expr_and.as_mut().unwrap())?;
+            let first_param_uncapsuled = match &first_param_type {
+                SemanticType::Array(e) => e.clone(),
+                _ => panic!("unreachable"),
+            };
+
+            // keys compare like `cmp_array` compares elements
+            match visit_second_param_func(node, &first_param_uncapsuled, None)? {
+                SemanticType::Primitive(_) | SemanticType::Tuple(_) | SemanticType::Array(_) => {
+                    Ok(first_param_type)
+                }
+                e => Err(format!("The second parameter of sort_by function must return a primitive, a tuple or an array! Currently, {:?} is returned!", e).into()),
+            }
+        }
+        // a use function applied to values, `category(x)`
+        _ if node.args.first().is_some_and(|x| x.value.is_none()) => visit_func_apply(node),
         _ => Err(format!("'{}' function not found!", &node.name).into()),
     };
 
@@ -374,6 +586,171 @@ fn visit_func(node: &mut FunctionExpressionNode) -> Result<SemanticType, Box<dyn
     result
 }
 
+// `ns="틀"` keeps the articles of one namespace, functions returning articles accept it.
+fn check_named_args(
+    node: &mut FunctionExpressionNode,
+    result_type: &SemanticType,
+) -> Result<(), Box<dyn Error>> {
+    for (name, arg) in node.named_args.iter_mut() {
+        if name != "ns" {
+            return Err(format!("'{}' function has no '{}' parameter!", &node.name, name).into());
+        }
+
+        let returns_articles = match result_type {
+            SemanticType::Array(e) => match e.as_ref() {
+                SemanticType::Primitive(SemanticPrimitiveType::Article) => true,
+                SemanticType::Tuple(e) => e[0]
+                    .as_ref()
+                    .eq(&SemanticType::Primitive(SemanticPrimitiveType::Article)),
+                _ => false,
+            },
+            _ => false,
+        };
+
+        if !returns_articles {
+            return Err(format!(
+                "'ns' parameter needs a function returning articles, '{}' returns '{:?}'.",
+                &node.name, result_type
+            )
+            .into());
+        }
+
+        match &arg.value {
+            Some(value) if Namespace::from_prefix(value).is_some() => {
+                arg.semantic_type = Some(SemanticType::Primitive(SemanticPrimitiveType::String));
+            }
+            Some(value) => return Err(format!("Unknown namespace '{}'.", value).into()),
+            None => return Err("'ns' parameter must be a namespace such as \"틀\".".into()),
+        }
+    }
+
+    Ok(())
+}
+
+// Return type of the second parameter of `map`, `filter` and `sort` called with `param1` and
+// `param2`, either a use function or a lambda.
+fn visit_second_param_func(
+    node: &mut FunctionExpressionNode,
+    param1: &SemanticType,
+    param2: Option<&SemanticType>,
+) -> Result<SemanticType, Box<dyn Error>> {
+    if let Some(lambda) = node.args[1].lambda.as_mut() {
+        let params: Vec<&SemanticType> = std::iter::once(param1).chain(param2).collect();
+
+        return visit_lambda(lambda, &params);
+    }
+
+    let second_param_func = get_second_param_func(node);
+
+    let semantic_type = visit_func_use(second_param_func)?;
+    let func_type = match semantic_type {
+        SemanticType::Function(e) => e,
+        _ => unreachable!(),
+    };
+
+    visit_infer_func_use(&func_type, Some(param1), param2)
+}
+
+fn visit_lambda(
+    node: &mut LambdaNode,
+    params: &[&SemanticType],
+) -> Result<SemanticType, Box<dyn Error>> {
+    if node.params.len() != params.len() {
+        return Err(format!(
+            "The lambda must have {} parameter(s)! Currently, it has {}.",
+            params.len(),
+            node.params.len()
+        )
+        .into());
+    }
+
+    for (name, semantic_type) in node.params.iter().zip(params) {
+        bind_variable(&mut node.body, name, semantic_type);
+    }
+
+    match visit_expr_and(&mut node.body)? {
+        SemanticType::Function(_) => {
+            Err("The lambda must return a value instead of a function!".into())
+        }
+        e => Ok(e),
+    }
+}
+
+// Give the references to `name` in `node` the type of the lambda parameter, lambdas with a
+// parameter of the same name shadow it.
+fn bind_variable(node: &mut ExpressionAndNode, name: &str, semantic_type: &SemanticType) {
+    for expr_case in node
+        .expr_ors
+        .iter_mut()
+        .flat_map(|x| x.expr_cases.iter_mut())
+    {
+        if let Some(expr_and) = &mut expr_case.expr_and {
+            bind_variable(expr_and, name, semantic_type);
+        }
+
+        if let Some(func) = &mut expr_case.func {
+            if func.is_use && func.name == name {
+                func.is_variable = true;
+                func.semantic_type = Some(semantic_type.clone());
+            }
+
+            for arg in func.args.iter_mut() {
+                if let Some(expr_and) = &mut arg.expr_and {
+                    bind_variable(expr_and, name, semantic_type);
+                }
+
+                if let Some(lambda) = &mut arg.lambda {
+                    if !lambda.params.iter().any(|x| x == name) {
+                        bind_variable(&mut lambda.body, name, semantic_type);
+                    }
+                }
+            }
+        }
+    }
+}
+
+// `category(x)`, `has_category(x, "게임")`, `cmp_array(a, b)`: a use function applied to the
+// leading values, the constants after them are its own.
+fn visit_func_apply(node: &mut FunctionExpressionNode) -> Result<SemanticType, Box<dyn Error>> {
+    let count = node.args.iter().take_while(|x| x.value.is_none()).count();
+    let mut values: Vec<Box<ArgumentNode>> = node.args.drain(..count).collect();
+
+    let func_type = visit_func_use(node);
+    node.args.splice(0..0, values.drain(..));
+
+    let func_type = match func_type? {
+        SemanticType::Function(e) => e,
+        _ => unreachable!(),
+    };
+
+    let expected = match func_type {
+        SemanticFunctionType::CmpArray
+        | SemanticFunctionType::CmpTuple1
+        | SemanticFunctionType::CmpTuple2 => 2,
+        _ => 1,
+    };
+
+    if node.args.len() != count && !is_predicate(&node.name) {
+        return Err(format!("'{}' function does not take constants!", &node.name).into());
+    }
+
+    if count != expected {
+        return Err(format!(
+            "'{}' function must be applied to {} value(s)!",
+            &node.name, expected
+        )
+        .into());
+    }
+
+    let mut types: Vec<SemanticType> = Vec::new();
+
+    for arg in node.args[..count].iter_mut() {
+        types.push(visit_arg(arg)?);
+    }
+
+    visit_infer_func_use(&func_type, types.first(), types.get(1))
+}
+
 fn get_second_param_func(node: &mut FunctionExpressionNode) -> &mut FunctionExpressionNode {
     node.args[1].expr_and.as_mut().unwrap().expr_ors[0].expr_cases[0]
         .func
@@ -388,19 +765,77 @@ fn visit_func_use(node: &mut FunctionExpressionNode) -> Result<SemanticType, Box
             Ok(SemanticType::Function(SemanticFunctionType::Select))
         }
         "redirect" => Ok(SemanticType::Function(SemanticFunctionType::Redirect)),
+        "outlinks" | "backlinks" => Ok(SemanticType::Function(SemanticFunctionType::Link)),
+        "is_redirect" => {
+            if !node.args.is_empty() {
+                return Err("'is_redirect' function must not have a parameter!".into());
+            }
+
+            Ok(SemanticType::Function(SemanticFunctionType::Predicate))
+        }
+        "has_category" | "body_contains" => {
+            param_check_lazy_1(
+                node,
+                &SemanticType::Primitive(SemanticPrimitiveType::String),
+            )?;
+
+            Ok(SemanticType::Function(SemanticFunctionType::Predicate))
+        }
+        "title_len_gt" | "title_len_lt" => {
+            param_check_lazy_1(
+                node,
+                &SemanticType::Primitive(SemanticPrimitiveType::Integer),
+            )?;
+
+            Ok(SemanticType::Function(SemanticFunctionType::Predicate))
+        }
         "unwrap_tuple1" => Ok(SemanticType::Function(SemanticFunctionType::UnwrapTuple1)),
         "unwrap_tuple2" => Ok(SemanticType::Function(SemanticFunctionType::UnwrapTuple2)),
         "cmp_array" => Ok(SemanticType::Function(SemanticFunctionType::CmpArray)),
         "cmp_tuple1" => Ok(SemanticType::Function(SemanticFunctionType::CmpTuple1)),
         "cmp_tuple2" => Ok(SemanticType::Function(SemanticFunctionType::CmpTuple2)),
+        "bind" => {
+            if node.args.is_empty() {
+                return Err("'bind' function must have at least one parameter!".into());
+            }
+
+            let mut funcs: Vec<SemanticFunctionType> = Vec::new();
+
+            for arg in node.args.iter_mut() {
+                match visit_arg(arg)? {
+                    SemanticType::Function(SemanticFunctionType::Lambda) => {
+                        return Err("'bind' function does not take lambdas yet!".into())
+                    }
+                    SemanticType::Function(e) => funcs.push(e),
+                    e => {
+                        return Err(format!(
+                        "'bind' function's parameters must be functions! Currently, {:?} is given.",
+                        e
+                    )
+                        .into())
+                    }
+                }
+            }
+
+            Ok(SemanticType::Function(SemanticFunctionType::Bind(funcs)))
+        }
         _ => Err(format!("'{}' function not found!", &node.name).into()),
     };
 
-    node.semantic_type = Some(result.as_ref().unwrap().clone());
+    if let Ok(e) = &result {
+        node.semantic_type = Some(e.clone());
+    }
 
     result
 }
 
+fn is_predicate(name: &str) -> bool {
+    matches!(
+        name,
+        "is_redirect" | "has_category" | "body_contains" | "title_len_gt" | "title_len_lt"
+    )
+}
+
 fn visit_infer_func_use(
     func: &SemanticFunctionType,
     param1: Option<&SemanticType>,
@@ -469,6 +904,31 @@ fn visit_infer_func_use(
                 Err(format!("'redirect' function must have one parameter").into())
             }
         }
+        SemanticFunctionType::Link => match param1 {
+            Some(SemanticType::Primitive(SemanticPrimitiveType::Article)) => Ok(
+                SemanticType::Array(Box::new(SemanticType::Primitive(
+                    SemanticPrimitiveType::Article,
+                ))),
+            ),
+            Some(p1) => Err(format!(
+                "'outlinks' and 'backlinks' function's first param type must be 'Article' instead of {:?}!",
+                p1,
+            )
+            .into()),
+            None => Err("'outlinks' and 'backlinks' function must have one parameter".into()),
+        },
+        SemanticFunctionType::Lambda => unreachable!(),
+        SemanticFunctionType::Predicate => match param1 {
+            Some(SemanticType::Primitive(SemanticPrimitiveType::Article)) => {
+                Ok(SemanticType::Primitive(SemanticPrimitiveType::Boolean))
+            }
+            Some(p1) => Err(format!(
+                "Predicate function's first param type must be 'Article' instead of {:?}!",
+                p1,
+            )
+            .into()),
+            None => Err("Predicate function must have one parameter".into()),
+        },
         SemanticFunctionType::UnwrapTuple1 => {
             if let Some(p1) = &param1 {
                 match p1 {
@@ -515,15 +975,41 @@ fn visit_infer_func_use(
                 Err(format!("'cmp_array' function must have two parameter").into())
             }
         }
+        SemanticFunctionType::Bind(funcs) => match (param1, param2) {
+            (Some(p1), None) => {
+                let mut result = p1.clone();
+
+                // each function takes the result of the previous one
+                for func in funcs {
+                    result = visit_infer_func_use(func, Some(&result), None)?;
+                }
+
+                Ok(result)
+            }
+            _ => Err("'bind' function must have one parameter".into()),
+        },
         SemanticFunctionType::CmpTuple1 | SemanticFunctionType::CmpTuple2 => {
-            if param1.is_some() && param2.is_some() {
-                if param1.unwrap().eq(&param2.unwrap()) {
+            let (name, field) = if matches!(func, SemanticFunctionType::CmpTuple1) {
+                ("cmp_tuple1", 0)
+            } else {
+                ("cmp_tuple2", 1)
+            };
+
+            match (param1, param2) {
+                (Some(p1), Some(p2)) if !p1.eq(p2) => {
+                    Err(format!("'{}' function must have same parameters type", name).into())
+                }
+                (Some(SemanticType::Tuple(e)), Some(_)) if e.len() > field => {
                     Ok(SemanticType::Primitive(SemanticPrimitiveType::Integer))
-                } else {
-                    Err(format!("'cmp_tuple1' function must have same parameters type").into())
                 }
-            } else {
-                Err(format!("'cmp_tuple1' function must have two parameter").into())
+                (Some(p1), Some(_)) => Err(format!(
+                    "'{}' function's param type must be a tuple of at least {} field(s) instead of {:?}!",
+                    name,
+                    field + 1,
+                    p1
+                )
+                .into()),
+                _ => Err(format!("'{}' function must have two parameter", name).into()),
             }
         }
     }
@@ -534,6 +1020,9 @@ fn visit_arg(node: &mut ArgumentNode) -> Result<SemanticType, Box<dyn Error>> {
         SemanticType::Primitive(SemanticPrimitiveType::String)
     } else if let Some(expr_and) = &mut node.expr_and {
         visit_expr_and(expr_and)?
+    } else if node.lambda.is_some() {
+        // typed by the function it is passed to
+        SemanticType::Function(SemanticFunctionType::Lambda)
     } else {
         panic!("unreachable")
     });
@@ -598,6 +1087,39 @@ fn param_check_lazy_2(
     }
 }
 
+fn param_check_lazy_3(
+    node: &mut FunctionExpressionNode,
+    first_target_type: &SemanticType,
+    second_target_type: &SemanticType,
+    third_target_type: &SemanticType,
+) -> Result<(), Box<dyn Error>> {
+    if node.args.len() != 3 {
+        Err(format!("'{}' function must have three parameter!", &node.name).into())
+    } else {
+        let targets = [first_target_type, second_target_type, third_target_type];
+
+        for (i, (order, target_type)) in ["first", "second", "third"]
+            .iter()
+            .zip(targets.iter())
+            .enumerate()
+        {
+            if !param_type_eq_generic(&mut node.args[i], target_type)? {
+                return Err(format!(
+                    "The {} parameter of '{}' function must be '{:?}' type! Current type is '{:?}'.",
+                    order,
+                    &node.name,
+                    target_type,
+                    visit_arg(&mut node.args[i])?
+                )
+                .into());
+            }
+        }
+
+        node.args[2].semantic_type = Some(third_target_type.clone());
+        Ok(())
+    }
+}
+
 fn param_type_eq_generic(
     arg: &mut ArgumentNode,
     target_type: &SemanticType,
@@ -639,6 +1161,8 @@ fn param_type_eq_generic(
             },
             _ => false,
         }
+    } else if arg.lambda.is_some() {
+        matches!(target_type, SemanticType::Function(_))
     } else {
         false
     })
@@ -648,7 +1172,7 @@ fn param_type_eq_generic(
 mod tests {
     use crate::core::{
         parser::Parser,
-        semantic::{SemanticPrimitiveType, SemanticType},
+        semantic::{SemanticFunctionType, SemanticPrimitiveType, SemanticType},
     };
 
     use super::check_semantic;
@@ -758,6 +1282,383 @@ mod tests {
         assert!(inferred_type.eq(&target_type));
     }
 
+    #[test]
+    fn type_infer_fuzzy_test() {
+        let mut p = Parser::from("title:fuzzy(\"동방프로잭트\", 2)");
+        let mut root = p.parse().unwrap();
+
+        let inferred_type = check_semantic(&mut root).unwrap();
+
+        let target_type = SemanticType::Array(Box::new(SemanticType::Tuple(vec![
+            Box::new(SemanticType::Primitive(SemanticPrimitiveType::Article)),
+            Box::new(SemanticType::Primitive(SemanticPrimitiveType::Integer)),
+        ])));
+
+        assert!(inferred_type.eq(&target_type));
+
+        let mut p = Parser::from("title:fuzzy(\"동방프로잭트\", \"2\")");
+        let mut root = p.parse().unwrap();
+
+        assert!(check_semantic(&mut root).is_ok());
+
+        let mut p = Parser::from("title:fuzzy(\"동방프로잭트\", \"a\")");
+        let mut root = p.parse().unwrap();
+
+        assert!(check_semantic(&mut root).is_err());
+    }
+
+    #[test]
+    fn type_infer_body_test() {
+        let mut p = Parser::from("count(body:regex(\"탄막\") & body:menu_exists(\"개요\"))");
+        let mut root = p.parse().unwrap();
+
+        let inferred_type = check_semantic(&mut root).unwrap();
+
+        assert!(inferred_type.eq(&SemanticType::Primitive(SemanticPrimitiveType::Integer)));
+    }
+
+    #[test]
+    fn type_infer_body_search_test() {
+        let target_type = SemanticType::Array(Box::new(SemanticType::Tuple(vec![
+            Box::new(SemanticType::Primitive(SemanticPrimitiveType::Article)),
+            Box::new(SemanticType::Primitive(SemanticPrimitiveType::Integer)),
+        ])));
+
+        let mut p = Parser::from("body:search(\"동방 프로젝트\")");
+        let mut root = p.parse().unwrap();
+
+        assert!(check_semantic(&mut root).unwrap().eq(&target_type));
+
+        let mut p = Parser::from("body:search(\"동방 프로젝트\", 100)");
+        let mut root = p.parse().unwrap();
+
+        assert!(check_semantic(&mut root).unwrap().eq(&target_type));
+
+        let mut p = Parser::from("body:search(\"동방 프로젝트\", \"a\")");
+        let mut root = p.parse().unwrap();
+
+        assert!(check_semantic(&mut root).is_err());
+    }
+
+    #[test]
+    fn type_infer_link_test() {
+        let mut p = Parser::from("flatten(map(title:exact(\"동방프로젝트\"), outlinks))");
+        let mut root = p.parse().unwrap();
+
+        let inferred_type = check_semantic(&mut root).unwrap();
+
+        assert!(
+            inferred_type.eq(&SemanticType::Array(Box::new(SemanticType::Primitive(
+                SemanticPrimitiveType::Article
+            ))))
+        );
+
+        let mut p = Parser::from("map(flatten(map(title:exact(\"동방\"), category)), backlinks)");
+        let mut root = p.parse().unwrap();
+
+        assert!(check_semantic(&mut root).is_err());
+    }
+
+    #[test]
+    fn type_infer_namespace_test() {
+        let check = |target| check_semantic(&mut Parser::from(target).parse().unwrap());
+        let articles = SemanticType::Array(Box::new(SemanticType::Primitive(
+            SemanticPrimitiveType::Article,
+        )));
+
+        assert!(check("title:contains(\"동방\", ns=\"틀\")")
+            .unwrap()
+            .eq(&articles));
+        assert!(
+            check("ns:category(title:contains(\"동방\") | links_to(\"동방\"))")
+                .unwrap()
+                .eq(&articles)
+        );
+        assert!(check("rank(body:contains(\"동방\"), ns=\"분류\")").is_ok());
+        assert!(check("title:contains(\"동방\", ns=\"없는 이름공간\")").is_err());
+        assert!(check("title:contains(\"동방\", lang=\"ko\")").is_err());
+        assert!(check("count(title:contains(\"동방\"), ns=\"틀\")").is_err());
+        assert!(check("ns:category(map(title:contains(\"동방\"), category))").is_err());
+    }
+
+    #[test]
+    fn type_infer_filter_test() {
+        let check = |target| check_semantic(&mut Parser::from(target).parse().unwrap());
+        let articles = SemanticType::Array(Box::new(SemanticType::Primitive(
+            SemanticPrimitiveType::Article,
+        )));
+
+        assert!(check("filter(title:contains(\"동방\"), is_redirect)")
+            .unwrap()
+            .eq(&articles));
+        assert!(check("count(filter(body:contains(\"동방\"), has_category(\"게임\")))").is_ok());
+        assert!(check("filter(title:contains(\"동방\"), title_len_gt(3))").is_ok());
+        assert!(check("filter(title:contains(\"동방\"), title_len_gt(\"세\"))").is_err());
+        assert!(check("filter(title:contains(\"동방\"), is_redirect(\"a\"))").is_err());
+        // the predicate must return Boolean
+        assert!(check("filter(title:contains(\"동방\"), category)").is_err());
+        assert!(check("filter(map(title:contains(\"동방\"), category), is_redirect)").is_err());
+    }
+
+    #[test]
+    fn type_infer_lambda_test() {
+        let check = |target| check_semantic(&mut Parser::from(target).parse().unwrap());
+        let integers = SemanticType::Array(Box::new(SemanticType::Primitive(
+            SemanticPrimitiveType::Integer,
+        )));
+
+        assert!(
+            check("map(title:contains(\"동방\"), x => count(category(x)))")
+                .unwrap()
+                .eq(&integers)
+        );
+        assert!(check("filter(title:contains(\"동방\"), x => has_category(x, \"게임\"))").is_ok());
+        assert!(check(
+            "map(title:contains(\"동방\"), x => count(filter(links_to(\"동방\"), y => has_category(x, \"게임\"))))"
+        )
+        .is_ok());
+        // wrong parameter count
+        assert!(check("map(title:contains(\"동방\"), (x, y) => count(category(x)))").is_err());
+        // the lambda must not return a function
+        assert!(check("map(title:contains(\"동방\"), x => category)").is_err());
+        // unbound name
+        assert!(check("map(title:contains(\"동방\"), x => count(category(y)))").is_err());
+        // the predicate must return Boolean
+        assert!(check("filter(title:contains(\"동방\"), x => category(x))").is_err());
+        // `|` and `&` between booleans are a logical or and and
+        assert!(check(
+            "filter(title:contains(\"동방\"), x => is_redirect(x) | has_category(x, \"게임\"))"
+        )
+        .is_ok());
+        assert!(check(
+            "filter(title:contains(\"동방\"), x => is_redirect(x) & has_category(x, \"게임\"))"
+        )
+        .is_ok());
+        assert!(
+            check("filter(title:contains(\"동방\"), x => is_redirect(x) & category(x))").is_err()
+        );
+        assert!(check(
+            "map(title:contains(\"동방\"), x => count(category(x)) & count(category(x)))"
+        )
+        .is_err());
+        // other primitives are concatenated into an array
+        assert!(check(
+            "map(title:contains(\"동방\"), x => select_max_len(category(x)) | category(x))"
+        )
+        .unwrap()
+        .eq(&SemanticType::Array(Box::new(SemanticType::Array(
+            Box::new(SemanticType::Primitive(SemanticPrimitiveType::Category))
+        )))));
+        assert!(check(
+            "filter(title:contains(\"동방\"), x => select_max_len(category(x)) | select_min_len(category(x)))"
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn semantic_type_eq_test() {
+        let none = || Box::new(SemanticType::None);
+        let integer = || Box::new(SemanticType::Primitive(SemanticPrimitiveType::Integer));
+        let function = SemanticType::Function(SemanticFunctionType::None);
+
+        // `None` is a generic parameter matching any value
+        for x in [
+            SemanticType::Primitive(SemanticPrimitiveType::Article),
+            SemanticType::Array(integer()),
+            SemanticType::Set(integer()),
+            SemanticType::Tuple(vec![integer(), integer()]),
+        ] {
+            assert!(SemanticType::None.eq(&x));
+            assert!(x.eq(&SemanticType::None));
+        }
+
+        assert!(
+            SemanticType::Array(none()).eq(&SemanticType::Array(Box::new(SemanticType::Tuple(
+                vec![integer(), integer()]
+            ))))
+        );
+        assert!(SemanticType::Tuple(vec![none(), integer()])
+            .eq(&SemanticType::Tuple(vec![integer(), integer()])));
+        assert!(
+            !SemanticType::Tuple(vec![none()]).eq(&SemanticType::Tuple(vec![integer(), integer()]))
+        );
+        assert!(!SemanticType::Array(none()).eq(&SemanticType::Set(integer())));
+        assert!(
+            !SemanticType::Array(integer()).eq(&SemanticType::Array(Box::new(
+                SemanticType::Array(integer())
+            )))
+        );
+        assert!(!SemanticType::None.eq(&function));
+        assert!(!SemanticType::Array(none()).eq(&function));
+    }
+
+    #[test]
+    fn type_infer_sort_test() {
+        let check = |target| check_semantic(&mut Parser::from(target).parse().unwrap());
+        let articles = SemanticType::Array(Box::new(SemanticType::Primitive(
+            SemanticPrimitiveType::Article,
+        )));
+
+        assert!(check("sort:desc(title:contains(\"동방\"), cmp_array)")
+            .unwrap()
+            .eq(&articles));
+        assert!(check("sort(title:contains(\"동방\"), (x, y) => cmp_array(y, x))").is_ok());
+        assert!(check("sort(group_sum(links_to(\"동방\")), cmp_tuple2)").is_ok());
+        assert!(check("sort_by(group_sum(links_to(\"동방\")), unwrap_tuple2)").is_ok());
+        assert!(
+            check("sort_by:desc(title:contains(\"동방\"), x => count(category(x)))")
+                .unwrap()
+                .eq(&articles)
+        );
+        // tuples only
+        assert!(check("sort(title:contains(\"동방\"), cmp_tuple1)").is_err());
+        // comparators take two elements, keys one
+        assert!(check("sort(title:contains(\"동방\"), x => count(category(x)))").is_err());
+        assert!(check("sort_by(title:contains(\"동방\"), cmp_array)").is_err());
+    }
+
+    #[test]
+    fn type_infer_bind_test() {
+        let check = |target| check_semantic(&mut Parser::from(target).parse().unwrap());
+
+        assert!(
+            check("map(title:contains(\"동방\"), bind(redirect, category))")
+                .unwrap()
+                .eq(&SemanticType::Array(Box::new(SemanticType::Array(
+                    Box::new(SemanticType::Primitive(SemanticPrimitiveType::Category))
+                ))))
+        );
+        assert!(check("filter(title:contains(\"동방\"), bind(redirect, is_redirect))").is_ok());
+        assert!(
+            check("filter(title:contains(\"동방\"), bind(redirect, has_category(\"게임\")))")
+                .is_ok()
+        );
+        assert!(check("map(title:contains(\"동방\"), bind(bind(redirect), outlinks))").is_ok());
+        // `category` returns categories, `redirect` takes an article
+        assert!(check("map(title:contains(\"동방\"), bind(category, redirect))").is_err());
+        assert!(check("filter(title:contains(\"동방\"), bind(redirect, category))").is_err());
+        assert!(check("sort(title:contains(\"동방\"), bind(cmp_array))").is_err());
+        assert!(check("map(title:contains(\"동방\"), bind(x => redirect(x)))").is_err());
+
+        // functions are not values outside of their argument position
+        assert!(check("has_category(\"게임\")").is_err());
+        assert!(check("bind(redirect, category)").is_err());
+        assert!(check("has_category(\"게임\") | title:contains(\"동방\")").is_err());
+        assert!(check("title:contains(\"동방\") & has_category(\"게임\")").is_err());
+        assert!(check("count(has_category(\"게임\"))").is_err());
+        assert!(check("group_sum(bind(redirect))").is_err());
+        assert!(check("map(has_category(\"게임\"), redirect)").is_err());
+    }
+
+    #[test]
+    fn type_infer_select_unwrap_test() {
+        let check = |target| check_semantic(&mut Parser::from(target).parse().unwrap());
+        let categories = SemanticType::Array(Box::new(SemanticType::Primitive(
+            SemanticPrimitiveType::Category,
+        )));
+
+        assert!(
+            check("map(map(title:contains(\"동방\"), category), select_max_len)")
+                .unwrap()
+                .eq(&categories)
+        );
+        assert!(check("map(group_sum(links_to(\"동방\")), unwrap_tuple2)")
+            .unwrap()
+            .eq(&SemanticType::Array(Box::new(SemanticType::Primitive(
+                SemanticPrimitiveType::Integer
+            )))));
+        assert!(check("map(title:contains(\"동방\"), unwrap_tuple1)").is_err());
+        assert!(check("map(title:contains(\"동방\"), select_min_len)").is_err());
+    }
+
+    #[test]
+    fn type_infer_links_to_test() {
+        let mut p = Parser::from("group_sum(links_to(\"동방프로젝트\"))");
+        let mut root = p.parse().unwrap();
+
+        let inferred_type = check_semantic(&mut root).unwrap();
+
+        assert!(
+            inferred_type.eq(&SemanticType::Array(Box::new(SemanticType::Tuple(vec![
+                Box::new(SemanticType::Primitive(SemanticPrimitiveType::Article)),
+                Box::new(SemanticType::Primitive(SemanticPrimitiveType::Integer)),
+            ]))))
+        );
+    }
+
+    #[test]
+    fn type_infer_rank_test() {
+        let mut p = Parser::from("rank(title:contains(\"동방\") | links_to(\"동방\"))");
+        let mut root = p.parse().unwrap();
+
+        let inferred_type = check_semantic(&mut root).unwrap();
+
+        assert!(
+            inferred_type.eq(&SemanticType::Array(Box::new(SemanticType::Tuple(vec![
+                Box::new(SemanticType::Primitive(SemanticPrimitiveType::Article)),
+                Box::new(SemanticType::Primitive(SemanticPrimitiveType::Integer)),
+            ]))))
+        );
+
+        let mut p = Parser::from("rank(flatten(map(title:contains(\"동방\"), category)))");
+        let mut root = p.parse().unwrap();
+
+        assert!(check_semantic(&mut root).is_err());
+    }
+
+    #[test]
+    fn type_infer_path_test() {
+        let target_type = SemanticType::Array(Box::new(SemanticType::Primitive(
+            SemanticPrimitiveType::Article,
+        )));
+
+        let mut p = Parser::from("path(\"동방프로젝트\", \"서든어택\")");
+        let mut root = p.parse().unwrap();
+
+        assert!(check_semantic(&mut root).unwrap().eq(&target_type));
+
+        let mut p = Parser::from("path(\"동방프로젝트\", \"서든어택\", 3)");
+        let mut root = p.parse().unwrap();
+
+        assert!(check_semantic(&mut root).unwrap().eq(&target_type));
+
+        let mut p = Parser::from("path(\"동방프로젝트\", \"서든어택\", \"a\")");
+        let mut root = p.parse().unwrap();
+
+        assert!(check_semantic(&mut root).is_err());
+    }
+
+    #[test]
+    fn type_infer_category_test() {
+        let mut p = Parser::from(
+            "map(category:startswith(\"일본 애니메이션/\") & category:exact(\"신카이 마코토\"), category)",
+        );
+        let mut root = p.parse().unwrap();
+
+        let inferred_type = check_semantic(&mut root).unwrap();
+
+        assert!(
+            inferred_type.eq(&SemanticType::Array(Box::new(SemanticType::Array(
+                Box::new(SemanticType::Primitive(SemanticPrimitiveType::Category))
+            ))))
+        );
+    }
+
+    #[test]
+    fn type_infer_category_descendants_test() {
+        let mut p = Parser::from("count(category:descendants(\"일본 애니메이션\", 2))");
+        let mut root = p.parse().unwrap();
+
+        let inferred_type = check_semantic(&mut root).unwrap();
+
+        assert!(inferred_type.eq(&SemanticType::Primitive(SemanticPrimitiveType::Integer)));
+
+        let mut p = Parser::from("category:descendants(\"일본 애니메이션\", \"a\")");
+        let mut root = p.parse().unwrap();
+
+        assert!(check_semantic(&mut root).is_err());
+    }
+
     #[test]
     fn type_infer_test_6() {
         let mut p =
@@ -12,6 +12,8 @@ pub enum TokenType {
     BraceStart, // (
     BraceEnd,   // )
     Comma,      // ,
+    Equal,      // =
+    Arrow,      // =>
     Name,       // [_a-zA-Z$][_:a-zA-Z0-9$]**
     Const,      // number ([0-9]+), string ("([^\\"]|\\")*")
 }
@@ -43,7 +45,14 @@ impl Tokenizer {
         }
 
         match self.target[self.ptr] {
-            '(' | ')' | '&' | '|' | ',' => {
+            '=' if self.target.get(self.ptr + 1) == Some(&'>') => {
+                self.ptr += 2;
+                Token {
+                    token_type: TokenType::Arrow,
+                    content: None,
+                }
+            }
+            '(' | ')' | '&' | '|' | ',' | '=' => {
                 self.ptr += 1;
                 Token {
                     token_type: match self.target[self.ptr - 1] {
@@ -52,6 +61,7 @@ impl Tokenizer {
                         '&' => TokenType::And,
                         '|' => TokenType::Or,
                         ',' => TokenType::Comma,
+                        '=' => TokenType::Equal,
                         _ => panic!(),
                     },
                     content: None,
@@ -158,8 +168,18 @@ impl Tokenizer {
     }
 
     pub fn lookup(&mut self) -> TokenType {
+        self.lookup_nth(1)
+    }
+
+    // Type of the n-th next token, `lookup_nth(1)` is `lookup()`.
+    pub fn lookup_nth(&mut self, n: usize) -> TokenType {
         let last_pos = self.ptr;
-        let lookup = self.next();
+        let mut lookup = self.next();
+
+        for _ in 1..n {
+            lookup = self.next();
+        }
+
         self.ptr = last_pos;
 
         lookup.token_type
@@ -193,4 +213,22 @@ mod tests {
         assert_eq!(tok.next().token_type, TokenType::BraceEnd);
         assert_eq!(tok.next().token_type, TokenType::Eof);
     }
+
+    #[test]
+    fn tokenizer_named_arg_test() {
+        let mut tok = Tokenizer::from("ns=\"틀\")");
+        assert_eq!(tok.lookup_nth(2), TokenType::Equal);
+        assert_eq!(tok.next().token_type, TokenType::Name);
+        assert_eq!(tok.next().token_type, TokenType::Equal);
+        assert_eq!(tok.next().token_type, TokenType::Const);
+        assert_eq!(tok.lookup_nth(2), TokenType::Eof);
+    }
+
+    #[test]
+    fn tokenizer_lambda_test() {
+        let mut tok = Tokenizer::from("x => count(x)");
+        assert_eq!(tok.next().token_type, TokenType::Name);
+        assert_eq!(tok.next().token_type, TokenType::Arrow);
+        assert_eq!(tok.next().token_type, TokenType::Name);
+    }
 }
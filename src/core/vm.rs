@@ -1,17 +1,26 @@
 use std::{
+    cell::RefCell,
+    cmp::{Ordering, Reverse},
     collections::{HashMap, HashSet},
     error::Error,
 };
 
+use regex::Regex;
+
 use crate::{
     index::{
-        category::CategoryIndex,
+        body::{strip_namumark, BodyIndex},
+        category::{CategoryFindOption, CategoryIndex},
+        category_tree::CategoryTree,
+        link::LinkIndex,
+        rank::RankIndex,
         title::{TitleIndex, TitleIndexFindOption},
     },
-    model::article::Article,
+    loader::load::DumpReader,
+    model::{article::Article, namespace::Namespace},
 };
 
-use super::ir::{Instruction, InstructionType};
+use super::ir::{IRBuilder, Instruction, InstructionType};
 
 #[derive(Clone, Debug, Eq, PartialEq, Hash)]
 pub enum RuntimeVariableAbstractPrimitiveData<'a> {
@@ -19,6 +28,7 @@ pub enum RuntimeVariableAbstractPrimitiveData<'a> {
     Category(&'a str),
     Integer(i64),
     String(String),
+    Boolean(bool),
 }
 
 #[derive(Clone, Debug, Eq, PartialEq, Hash)]
@@ -66,6 +76,7 @@ impl<'a> RuntimeVariableAbstractPrimitiveData<'a> {
     unwrap_rvp!(Category, str);
     unwrap_rvp!(Integer, i64);
     unwrap_rvp!(String, String);
+    unwrap_rvp!(Boolean, bool);
 }
 
 impl<'a> RuntimeVariableAbstractData<'a> {
@@ -81,12 +92,167 @@ pub struct RuntimeVariable<'a> {
     pub data: RuntimeVariableAbstractData<'a>,
 }
 
+// Number of results of `body:search` without an explicit size.
+const BODY_SEARCH_LIMIT: usize = 10;
+
+// `body:search` returns BM25 scores as integers, multiplied by this and rounded.
+const BODY_SEARCH_SCORE_SCALE: f64 = 1000.0;
+
+// Number of links `path` may follow without an explicit limit.
+const PATH_DEPTH_LIMIT: usize = 6;
+
+// Levels of subcategories `category:descendants` walks without an explicit limit.
+const CATEGORY_DEPTH_LIMIT: usize = 10;
+
 pub struct RuntimeRef<'a> {
     pub category_index: &'a CategoryIndex,
+    pub category_tree: Option<&'a CategoryTree>,
     pub title_index: &'a TitleIndex,
+    pub body_index: Option<&'a BodyIndex>,
+    pub link_index: Option<&'a LinkIndex>,
+    pub rank_index: Option<&'a RankIndex>,
     pub articles: Option<Vec<Article>>,
 }
 
+// The body of a lambda, flattened once and evaluated once per call with the parameters bound.
+struct Lambda<'a> {
+    insts: Vec<&'a Instruction>,
+    // references to each parameter, in nested lambdas too
+    params: Vec<Vec<&'a Instruction>>,
+    // references to parameters of enclosing lambdas, bound in the caller's variables
+    captures: Vec<&'a Instruction>,
+    // instructions using a parameter, the others are evaluated on the first call and kept
+    dependent: HashSet<usize>,
+    variables: RefCell<HashMap<usize, RuntimeVariable<'a>>>,
+}
+
+impl<'a> Lambda<'a> {
+    fn new(inst: &'a Instruction) -> Self {
+        let names: Vec<&str> = inst.data.as_ref().unwrap().split(',').collect();
+        let mut variables: Vec<&'a Instruction> = Vec::new();
+
+        Self::collect_variables(
+            &inst.params.as_ref().unwrap()[0],
+            &mut Vec::new(),
+            &mut variables,
+        );
+
+        let mut params: Vec<Vec<&'a Instruction>> = vec![Vec::new(); names.len()];
+        let mut captures: Vec<&'a Instruction> = Vec::new();
+
+        for x in variables {
+            match names
+                .iter()
+                .position(|name| x.data.as_ref().unwrap() == name)
+            {
+                Some(i) => params[i].push(x),
+                None => captures.push(x),
+            }
+        }
+
+        let mut dependent = HashSet::new();
+
+        Self::collect_dependent(
+            &inst.params.as_ref().unwrap()[0],
+            &params.iter().flatten().map(|x| x.id).collect(),
+            &mut dependent,
+        );
+
+        Lambda {
+            insts: IRBuilder::ir_flatten_lambda(inst),
+            params,
+            captures,
+            dependent,
+            variables: RefCell::new(HashMap::new()),
+        }
+    }
+
+    // Instructions of `inst` using one of `params`, returns whether `inst` does.
+    fn collect_dependent(
+        inst: &Instruction,
+        params: &HashSet<usize>,
+        result: &mut HashSet<usize>,
+    ) -> bool {
+        let mut dependent = params.contains(&inst.id);
+
+        for x in inst.params.iter().flatten() {
+            dependent |= Self::collect_dependent(x, params, result);
+        }
+
+        if dependent {
+            result.insert(inst.id);
+        }
+
+        dependent
+    }
+
+    // Variables of `inst` not bound by a lambda inside it.
+    fn collect_variables(
+        inst: &'a Instruction,
+        shadowed: &mut Vec<&'a str>,
+        result: &mut Vec<&'a Instruction>,
+    ) {
+        match inst.inst_type {
+            InstructionType::Variable => {
+                if !shadowed.contains(&&inst.data.as_ref().unwrap()[..]) {
+                    result.push(inst);
+                }
+            }
+            InstructionType::Lambda => {
+                let len = shadowed.len();
+                shadowed.extend(inst.data.as_ref().unwrap().split(','));
+                Self::collect_variables(&inst.params.as_ref().unwrap()[0], shadowed, result);
+                shadowed.truncate(len);
+            }
+            _ => {
+                for x in inst.params.iter().flatten() {
+                    Self::collect_variables(x, shadowed, result);
+                }
+            }
+        }
+    }
+
+    fn call(
+        &self,
+        vm: &VirtualMachine,
+        var: &HashMap<usize, RuntimeVariable<'a>>,
+        reference: &'a RuntimeRef,
+        args: &[&RuntimeVariableAbstractData<'a>],
+    ) -> Result<RuntimeVariableAbstractData<'a>, Box<dyn Error>> {
+        let mut rt_var = self.variables.borrow_mut();
+
+        // captures are the same on every call, the caller's variables do not change
+        for x in self.captures.iter() {
+            rt_var.entry(x.id).or_insert_with(|| var[&x.id].clone());
+        }
+
+        // results of the last call
+        rt_var.retain(|id, _| !self.dependent.contains(id));
+
+        for (variables, arg) in self.params.iter().zip(args) {
+            for x in variables {
+                let data = (*arg).clone();
+                rt_var.insert(x.id, RuntimeVariable { inst: x, data });
+            }
+        }
+
+        for inst in self.insts.iter() {
+            if !rt_var.contains_key(&inst.id) {
+                let result = vm.eval_inst(&rt_var, reference, inst)?;
+                rt_var.insert(inst.id, result);
+            }
+        }
+
+        let id = self.insts.last().unwrap().id;
+
+        Ok(if self.dependent.contains(&id) {
+            rt_var.remove(&id).unwrap().data
+        } else {
+            rt_var[&id].data.clone()
+        })
+    }
+}
+
 pub struct VirtualMachine<'a> {
     insts: Vec<&'a Instruction>,
     debug: bool,
@@ -130,10 +296,26 @@ impl VirtualMachine<'_> {
         reference: &'a RuntimeRef,
         inst: &'a Instruction,
     ) -> Result<RuntimeVariable<'a>, Box<dyn Error>> {
+        // nothing in, nothing out, `select_*` of an empty array is nothing
+        let nothing = inst
+            .params
+            .iter()
+            .flatten()
+            .filter_map(|x| var.get(&x.id))
+            .any(|x| matches!(x.data, RuntimeVariableAbstractData::None));
+
+        if nothing {
+            return Ok(RuntimeVariable {
+                inst,
+                data: RuntimeVariableAbstractData::None,
+            });
+        }
+
         match inst.inst_type {
             InstructionType::FunctionCall => self.eval_func(var, reference, inst),
             InstructionType::Intercross => self.eval_intercross(var, inst),
             InstructionType::Concat => self.eval_concat(var, inst),
+            // lambda parameters are bound before the body is evaluated
             _ => unreachable!(),
         }
     }
@@ -144,42 +326,126 @@ impl VirtualMachine<'_> {
         reference: &'a RuntimeRef,
         inst: &'a Instruction,
     ) -> Result<RuntimeVariable<'a>, Box<dyn Error>> {
-        match &inst.data.as_ref().unwrap()[..] {
-            "title" | "title:contains" | "title:statswith" | "title:endswith" => {
+        let result = match &inst.data.as_ref().unwrap()[..] {
+            "title" | "title:exact" | "title:contains" | "title:startswith" | "title:endswith"
+            | "title:chosung" | "title:jamo_prefix" | "title:fuzzy" => {
                 self.eval_func_title(reference, inst)
             }
-            "body:contains" | "body:menu_exists" => {
-                if reference.articles.is_none() {
-                    panic!("you must load dump if you want to use body related func!")
-                }
-
+            "body:contains" | "body:regex" | "body:menu_exists" => {
                 self.eval_func_body(reference, inst)
             }
+            "body:search" => self.eval_func_body_search(reference, inst),
+            "links_to" => self.eval_func_links_to(reference, inst),
+            "redirects_to" => self.eval_func_redirects_to(reference, inst),
+            "rank" => self.eval_func_rank(var, reference, inst),
+            "path" => self.eval_func_path(reference, inst),
+            "category:exact" | "category:startswith" | "category:contains" => {
+                self.eval_func_category(reference, inst)
+            }
+            "category:descendants" => self.eval_func_category_descendants(reference, inst),
             "count" => self.eval_func_count(var, inst),
             "set" => self.eval_func_set(var, inst),
             "group_sum" => self.eval_func_group_sum(var, inst),
             "map" => self.eval_func_map(var, reference, inst),
             "flatten" => self.eval_func_flatten(var, reference, inst),
+            "filter" => self.eval_func_filter(var, reference, inst),
+            "sort" | "sort:asc" | "sort:desc" | "sort_by" | "sort_by:asc" | "sort_by:desc" => {
+                self.eval_func_sort(var, reference, inst)
+            }
+            "category" | "redirect" | "outlinks" | "backlinks" | "is_redirect" | "has_category"
+            | "body_contains" | "title_len_gt" | "title_len_lt" | "unwrap_tuple1"
+            | "unwrap_tuple2" | "cmp_array" | "cmp_tuple1" | "cmp_tuple2" | "select_max_len"
+            | "select_min_len" => self.eval_func_apply(var, reference, inst),
+            "ns:document" | "ns:template" | "ns:category" | "ns:file" | "ns:user" | "ns:wiki" => {
+                self.eval_func_namespace(var, inst)
+            }
             _ => unreachable!(),
+        }?;
+
+        match Self::namespace_param(inst) {
+            Some(namespace) => Ok(Self::filter_namespace(result, namespace)),
+            None => Ok(result),
+        }
+    }
+
+    // `ns="틀"`, checked by the semantic analysis.
+    fn namespace_param(inst: &Instruction) -> Option<Namespace> {
+        inst.named_params
+            .iter()
+            .find(|x| x.0 == "ns")
+            .and_then(|x| Namespace::from_prefix(&x.1))
+    }
+
+    // Keep the articles, or tuples starting with an article, of `namespace`.
+    fn filter_namespace(var: RuntimeVariable, namespace: Namespace) -> RuntimeVariable {
+        fn in_namespace(data: &RuntimeVariableAbstractData, namespace: Namespace) -> bool {
+            match data {
+                RuntimeVariableAbstractData::Primitive(
+                    RuntimeVariableAbstractPrimitiveData::Article(article),
+                ) => article.namespace == namespace,
+                RuntimeVariableAbstractData::Tuple(e) => in_namespace(&e[0], namespace),
+                _ => false,
+            }
+        }
+
+        match var.data {
+            RuntimeVariableAbstractData::Array(e) => RuntimeVariable {
+                inst: var.inst,
+                data: RuntimeVariableAbstractData::Array(Box::new(
+                    e.into_iter()
+                        .filter(|x| in_namespace(x, namespace))
+                        .collect(),
+                )),
+            },
+            _ => var,
         }
     }
 
+    fn eval_func_namespace<'a>(
+        &self,
+        var: &HashMap<usize, RuntimeVariable<'a>>,
+        inst: &'a Instruction,
+    ) -> Result<RuntimeVariable<'a>, Box<dyn Error>> {
+        let namespace = Namespace::from_name(&inst.data.as_ref().unwrap()["ns:".len()..]).unwrap();
+        let func_id = &inst.params.as_ref().unwrap()[0].id;
+
+        Ok(Self::filter_namespace(
+            RuntimeVariable {
+                inst,
+                data: var[func_id].data.clone(),
+            },
+            namespace,
+        ))
+    }
+
     fn eval_intercross<'a>(
         &self,
         var: &HashMap<usize, RuntimeVariable<'a>>,
         inst: &'a Instruction,
     ) -> Result<RuntimeVariable<'a>, Box<dyn Error>> {
+        let params = inst.params.as_ref().unwrap();
+
+        // `&` between booleans is a logical and
+        if let Some(booleans) = Self::booleans(var, params) {
+            return Ok(RuntimeVariable {
+                inst,
+                data: RuntimeVariableAbstractData::Primitive(
+                    RuntimeVariableAbstractPrimitiveData::Boolean(booleans.iter().all(|x| *x)),
+                ),
+            });
+        }
+
         let mut intersection_count: HashMap<&RuntimeVariableAbstractData, usize> = HashMap::new();
 
-        inst.params.as_ref().unwrap().iter().for_each(|x| {
-            let array = &var[&x.id].data.unwrap_array();
+        params.iter().for_each(|x| {
+            let elements = Self::elements(&var[&x.id].data);
 
-            array.iter().for_each(|x| {
+            elements.iter().for_each(|x| {
                 *intersection_count.entry(x).or_default() += 1;
             });
         });
 
-        let max_count = inst.params.as_ref().unwrap().len();
+        let max_count = params.len();
 
         let result: Vec<RuntimeVariableAbstractData> = intersection_count
             .iter()
@@ -189,7 +455,7 @@ impl VirtualMachine<'_> {
 
         Ok(RuntimeVariable {
             inst,
-            data: RuntimeVariableAbstractData::Array(Box::new(result)),
+            data: Self::collection(var, params, result),
         })
     }
 
@@ -198,22 +464,83 @@ impl VirtualMachine<'_> {
         var: &HashMap<usize, RuntimeVariable<'a>>,
         inst: &'a Instruction,
     ) -> Result<RuntimeVariable<'a>, Box<dyn Error>> {
-        let mut result: Vec<RuntimeVariableAbstractData> = Vec::new();
+        let params = inst.params.as_ref().unwrap();
+
+        // `|` between booleans is a logical or
+        if let Some(booleans) = Self::booleans(var, params) {
+            return Ok(RuntimeVariable {
+                inst,
+                data: RuntimeVariableAbstractData::Primitive(
+                    RuntimeVariableAbstractPrimitiveData::Boolean(booleans.iter().any(|x| *x)),
+                ),
+            });
+        }
 
-        inst.params.as_ref().unwrap().iter().for_each(|x| {
-            let array = &var[&x.id].data.unwrap_array();
+        let mut result: Vec<RuntimeVariableAbstractData> = Vec::new();
 
-            array.as_ref().iter().for_each(|x| {
-                result.push(x.clone());
-            });
+        params.iter().for_each(|x| match &var[&x.id].data {
+            RuntimeVariableAbstractData::Array(e) | RuntimeVariableAbstractData::Set(e) => {
+                e.iter().for_each(|x| result.push(x.clone()));
+            }
+            // primitives and tuples are concatenated as single elements
+            e => result.push(e.clone()),
         });
 
         Ok(RuntimeVariable {
-            inst: inst,
-            data: RuntimeVariableAbstractData::Array(Box::new(result)),
+            inst,
+            data: Self::collection(var, params, result),
         })
     }
 
+    // values of the operands, if all of them are booleans
+    fn booleans(
+        var: &HashMap<usize, RuntimeVariable>,
+        params: &[Box<Instruction>],
+    ) -> Option<Vec<bool>> {
+        params
+            .iter()
+            .map(|x| match &var[&x.id].data {
+                RuntimeVariableAbstractData::Primitive(
+                    RuntimeVariableAbstractPrimitiveData::Boolean(e),
+                ) => Some(*e),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn elements<'a, 'b>(
+        data: &'b RuntimeVariableAbstractData<'a>,
+    ) -> &'b [RuntimeVariableAbstractData<'a>] {
+        match data {
+            RuntimeVariableAbstractData::Array(e) | RuntimeVariableAbstractData::Set(e) => e,
+            e => std::slice::from_ref(e),
+        }
+    }
+
+    // the result of `|` and `&` is a set if any operand is a set
+    fn collection<'a>(
+        var: &HashMap<usize, RuntimeVariable<'a>>,
+        params: &[Box<Instruction>],
+        result: Vec<RuntimeVariableAbstractData<'a>>,
+    ) -> RuntimeVariableAbstractData<'a> {
+        let is_set = params
+            .iter()
+            .any(|x| matches!(var[&x.id].data, RuntimeVariableAbstractData::Set(_)));
+
+        if !is_set {
+            return RuntimeVariableAbstractData::Array(Box::new(result));
+        }
+
+        let mut set: HashSet<RuntimeVariableAbstractData> = HashSet::new();
+
+        RuntimeVariableAbstractData::Set(Box::new(
+            result
+                .into_iter()
+                .filter(|x| set.insert(x.clone()))
+                .collect(),
+        ))
+    }
+
     fn eval_func_title<'a>(
         &self,
         reference: &RuntimeRef,
@@ -239,17 +566,25 @@ impl VirtualMachine<'_> {
                     Err(format!("Cannot found title '{}'", what).into())
                 }
             }
-            "title:exact" | "title:contains" | "title:statswith" | "title:endswith" => {
-                let titles = reference.title_index.find_by(
-                    what,
-                    match &inst.data.as_ref().unwrap()[..] {
-                        "title:exact" => TitleIndexFindOption::Extact,
-                        "title:contains" => TitleIndexFindOption::Contains,
-                        "title:startswith" => TitleIndexFindOption::StartsWith,
-                        "title:endswith" => TitleIndexFindOption::EndsWith,
-                        _ => unreachable!(),
-                    },
-                );
+            "title:exact" | "title:contains" | "title:startswith" | "title:endswith"
+            | "title:chosung" | "title:jamo_prefix" => {
+                let option = match &inst.data.as_ref().unwrap()[..] {
+                    "title:exact" => TitleIndexFindOption::Extact,
+                    "title:contains" => TitleIndexFindOption::Contains,
+                    "title:startswith" => TitleIndexFindOption::StartsWith,
+                    "title:endswith" => TitleIndexFindOption::EndsWith,
+                    "title:chosung" => TitleIndexFindOption::Chosung,
+                    "title:jamo_prefix" => TitleIndexFindOption::JamoPrefix,
+                    _ => unreachable!(),
+                };
+
+                // only the range of the namespace is searched
+                let titles = match Self::namespace_param(inst) {
+                    Some(namespace) => reference
+                        .title_index
+                        .find_by_namespace(what, option, namespace),
+                    None => reference.title_index.find_by(what, option),
+                };
 
                 if titles.is_empty() {
                     Err(format!("Cannot found title '{}'", what).into())
@@ -271,6 +606,40 @@ impl VirtualMachine<'_> {
                     })
                 }
             }
+            "title:fuzzy" => {
+                let max_distance: usize = inst.params.as_ref().unwrap()[1]
+                    .data
+                    .as_ref()
+                    .unwrap()
+                    .parse()?;
+
+                let titles = reference.title_index.find_fuzzy(what, max_distance);
+
+                if titles.is_empty() {
+                    Err(format!("Cannot found title '{}'", what).into())
+                } else {
+                    let articles: Vec<RuntimeVariableAbstractData> = titles
+                        .iter()
+                        .map(|(t, distance)| {
+                            let article = reference.title_index.get_no_redirect(t).unwrap();
+
+                            RuntimeVariableAbstractData::Tuple(vec![
+                                RuntimeVariableAbstractData::Primitive(
+                                    RuntimeVariableAbstractPrimitiveData::Article(article),
+                                ),
+                                RuntimeVariableAbstractData::Primitive(
+                                    RuntimeVariableAbstractPrimitiveData::Integer(*distance as i64),
+                                ),
+                            ])
+                        })
+                        .collect();
+
+                    Ok(RuntimeVariable {
+                        inst,
+                        data: RuntimeVariableAbstractData::Array(Box::new(articles)),
+                    })
+                }
+            }
             _ => unreachable!(),
         }
     }
@@ -282,130 +651,892 @@ impl VirtualMachine<'_> {
     ) -> Result<RuntimeVariable<'a>, Box<dyn Error>> {
         let what = inst.params.as_ref().unwrap()[0].data.as_ref().unwrap();
 
-        match &inst.data.as_ref().unwrap()[..] {
+        let matcher: Box<dyn Fn(&Article) -> bool> = match &inst.data.as_ref().unwrap()[..] {
+            // the rendered text, which is what the body index is built from
+            "body:contains" => {
+                Box::new(move |article| strip_namumark(&article.text).contains(&what[..]))
+            }
+            "body:regex" => {
+                let re = Regex::new(what)?;
+                Box::new(move |article| re.is_match(&article.text))
+            }
+            "body:menu_exists" => {
+                Box::new(move |article| article.menus().iter().any(|x| x == what))
+            }
             _ => unreachable!(),
+        };
+
+        // redirect documents have no body
+        let mut articles: Vec<RuntimeVariableAbstractData> = Vec::new();
+        let mut push = |article: &Article| {
+            if !article.is_redirect() && matcher(article) {
+                articles.push(RuntimeVariableAbstractData::Primitive(
+                    RuntimeVariableAbstractPrimitiveData::Article(article.clone()),
+                ));
+            }
+        };
+
+        // the body index only narrows down `body:contains`, candidates are verified above
+        let candidates = match reference.body_index {
+            Some(index) if &inst.data.as_ref().unwrap()[..] == "body:contains" => {
+                index.candidates(what)?.map(|x| (index, x))
+            }
+            _ => None,
+        };
+
+        // scan the loaded articles, otherwise stream the dump
+        match (&reference.articles, candidates) {
+            (Some(loaded), _) => loaded.iter().for_each(push),
+            (None, Some((index, candidates))) => {
+                for doc in candidates {
+                    push(&index.article(doc)?);
+                }
+            }
+            (None, None) => {
+                for entry in DumpReader::open(reference.title_index.dump_path())? {
+                    push(&entry?.article);
+                }
+            }
         }
+
+        Ok(RuntimeVariable {
+            inst,
+            data: RuntimeVariableAbstractData::Array(Box::new(articles)),
+        })
     }
 
-    fn eval_func_count<'a>(
+    fn eval_func_body_search<'a>(
         &self,
-        var: &HashMap<usize, RuntimeVariable<'a>>,
+        reference: &RuntimeRef,
         inst: &'a Instruction,
     ) -> Result<RuntimeVariable<'a>, Box<dyn Error>> {
-        let var = var.get(&inst.params.as_ref().unwrap()[0].id).unwrap();
-
-        let result = match &var.data {
-            RuntimeVariableAbstractData::Array(e) => e.len(),
-            RuntimeVariableAbstractData::Set(e) => e.len(),
-            _ => unreachable!(),
+        let params = inst.params.as_ref().unwrap();
+        let what = params[0].data.as_ref().unwrap();
+        let limit: usize = match params.get(1) {
+            Some(param) => param.data.as_ref().unwrap().parse()?,
+            None => BODY_SEARCH_LIMIT,
         };
 
+        let index = reference.body_index.ok_or(
+            "'body:search' needs the body index, build it with `hakurei index body` first",
+        )?;
+
+        // BM25 scores are kept as integers, see `BODY_SEARCH_SCORE_SCALE`
+        let mut articles: Vec<RuntimeVariableAbstractData> = Vec::new();
+
+        for (doc, score) in index.search(what)?.into_iter().take(limit) {
+            articles.push(RuntimeVariableAbstractData::Tuple(vec![
+                RuntimeVariableAbstractData::Primitive(
+                    RuntimeVariableAbstractPrimitiveData::Article(index.article(doc)?),
+                ),
+                RuntimeVariableAbstractData::Primitive(
+                    RuntimeVariableAbstractPrimitiveData::Integer(
+                        (score * BODY_SEARCH_SCORE_SCALE).round() as i64,
+                    ),
+                ),
+            ]));
+        }
+
         Ok(RuntimeVariable {
             inst,
-            data: RuntimeVariableAbstractData::Primitive(
-                RuntimeVariableAbstractPrimitiveData::Integer(result as i64),
-            ),
+            data: RuntimeVariableAbstractData::Array(Box::new(articles)),
         })
     }
 
-    // transform array to set
-    fn eval_func_set<'a>(
+    fn eval_func_links_to<'a>(
         &self,
-        var: &HashMap<usize, RuntimeVariable<'a>>,
+        reference: &RuntimeRef,
         inst: &'a Instruction,
     ) -> Result<RuntimeVariable<'a>, Box<dyn Error>> {
-        let mut set: HashSet<&RuntimeVariableAbstractData> = HashSet::new();
-        let mut result: Vec<RuntimeVariableAbstractData> = Vec::new();
+        let what = inst.params.as_ref().unwrap()[0].data.as_ref().unwrap();
 
-        let func_id = &inst.params.as_ref().unwrap()[0].id;
-        let array = &var[func_id].data.unwrap_array();
+        let index = reference
+            .link_index
+            .ok_or("'links_to' needs the link index, build it with `hakurei index link` first")?;
+
+        // links point to the article a redirect resolves to
+        let id = reference
+            .title_index
+            .get(what)
+            .and_then(|x| reference.title_index.id(&x.title));
+
+        // one article per link, so `group_sum` counts how often each source links
+        let mut articles: Vec<RuntimeVariableAbstractData> = Vec::new();
+        let mut last: Option<(u32, Article)> = None;
+
+        for source in id.into_iter().flat_map(|id| index.in_edges(id)) {
+            let article = match &last {
+                Some((id, article)) if *id == source => article.clone(),
+                _ => match reference
+                    .title_index
+                    .get_no_redirect(reference.title_index.title(source as usize))
+                {
+                    Some(article) => article,
+                    None => continue,
+                },
+            };
 
-        array.iter().for_each(|x| {
-            if !set.contains(x) {
-                set.insert(x);
-                // todo: this code maybe mem copy, mem allocation overhead
-                // we must consume target runtime variable, if that variable
-                // unused anymore
-                result.push(x.clone());
-            }
-        });
+            last = Some((source, article.clone()));
+            articles.push(RuntimeVariableAbstractData::Primitive(
+                RuntimeVariableAbstractPrimitiveData::Article(article),
+            ));
+        }
 
         Ok(RuntimeVariable {
             inst,
-            data: RuntimeVariableAbstractData::Set(Box::new(result)),
+            data: RuntimeVariableAbstractData::Array(Box::new(articles)),
         })
     }
 
-    fn eval_func_group_sum<'a>(
+    fn eval_func_redirects_to<'a>(
         &self,
-        var: &HashMap<usize, RuntimeVariable<'a>>,
+        reference: &RuntimeRef,
         inst: &'a Instruction,
     ) -> Result<RuntimeVariable<'a>, Box<dyn Error>> {
-        let mut group_map_index: HashMap<&RuntimeVariableAbstractData, usize> = HashMap::new();
-        let mut group_map_count: HashMap<usize, usize> = HashMap::new();
+        let what = inst.params.as_ref().unwrap()[0].data.as_ref().unwrap();
 
-        let func_id = &inst.params.as_ref().unwrap()[0].id;
-        let array = &var[func_id].data.unwrap_array();
+        let table = reference.title_index.redirect_table().ok_or(
+            "'redirects_to' needs the redirect table, build it with `hakurei index redirect` first",
+        )?;
+
+        // aliases of the article `what` ends at, so an alias lists its siblings
+        let id = reference
+            .title_index
+            .get(what)
+            .and_then(|x| reference.title_index.id(&x.title));
+
+        let articles: Vec<RuntimeVariableAbstractData> = id
+            .into_iter()
+            .flat_map(|id| table.aliases(id))
+            .filter_map(|x| {
+                reference
+                    .title_index
+                    .get_no_redirect(reference.title_index.title(x as usize))
+            })
+            .map(|x| {
+                RuntimeVariableAbstractData::Primitive(
+                    RuntimeVariableAbstractPrimitiveData::Article(x),
+                )
+            })
+            .collect();
 
-        for (i, e) in array.iter().enumerate() {
-            let index = group_map_index.entry(e).or_insert(i);
-            *group_map_count.entry(*index).or_default() += 1;
-        }
+        Ok(RuntimeVariable {
+            inst,
+            data: RuntimeVariableAbstractData::Array(Box::new(articles)),
+        })
+    }
 
-        let mut result: Vec<RuntimeVariableAbstractData> = Vec::new();
+    fn eval_func_path<'a>(
+        &self,
+        reference: &RuntimeRef,
+        inst: &'a Instruction,
+    ) -> Result<RuntimeVariable<'a>, Box<dyn Error>> {
+        let params = inst.params.as_ref().unwrap();
+        let from = params[0].data.as_ref().unwrap();
+        let to = params[1].data.as_ref().unwrap();
+        let depth: usize = match params.get(2) {
+            Some(param) => param.data.as_ref().unwrap().parse()?,
+            None => PATH_DEPTH_LIMIT,
+        };
 
-        for (i, e) in array.iter().enumerate() {
-            if group_map_index[e] == i {
-                let tuple_left = e.clone();
-                let tuple_right = RuntimeVariableAbstractData::Primitive(
-                    RuntimeVariableAbstractPrimitiveData::Integer(group_map_count[&i] as i64),
-                );
+        let index = reference
+            .link_index
+            .ok_or("'path' needs the link index, build it with `hakurei index link` first")?;
+
+        // both ends are resolved through redirects, as links are
+        let id = |title: &str| {
+            reference
+                .title_index
+                .get(title)
+                .and_then(|x| reference.title_index.id(&x.title))
+                .ok_or(format!("'{}' article is not found", title))
+        };
 
-                result.push(RuntimeVariableAbstractData::Tuple(vec![
-                    tuple_left,
-                    tuple_right,
-                ]));
-            }
+        let path = index
+            .shortest_path(id(from)?, id(to)?, depth)
+            .ok_or(format!(
+                "No path from '{}' to '{}' within {} links",
+                from, to, depth
+            ))?;
+
+        let mut articles: Vec<RuntimeVariableAbstractData> = Vec::new();
+
+        for id in path {
+            let title = reference.title_index.title(id as usize);
+            let article = reference
+                .title_index
+                .get_no_redirect(title)
+                .ok_or(format!("'{}' article is not found", title))?;
+
+            articles.push(RuntimeVariableAbstractData::Primitive(
+                RuntimeVariableAbstractPrimitiveData::Article(article),
+            ));
         }
 
-        result.sort_by(|x, y| {
-            let xx = x.unwrap_tuple()[1].unwrap_primitive().unwrap_integer();
-            let yy = y.unwrap_tuple()[1].unwrap_primitive().unwrap_integer();
-
-            yy.cmp(&xx)
-        });
-
         Ok(RuntimeVariable {
             inst,
-            data: RuntimeVariableAbstractData::Array(Box::new(result)),
+            data: RuntimeVariableAbstractData::Array(Box::new(articles)),
         })
     }
 
-    fn eval_func_map<'a>(
+    fn eval_func_category<'a>(
         &self,
-        var: &HashMap<usize, RuntimeVariable<'a>>,
-        reference: &'a RuntimeRef,
+        reference: &RuntimeRef,
         inst: &'a Instruction,
     ) -> Result<RuntimeVariable<'a>, Box<dyn Error>> {
-        match &inst.params.as_ref().unwrap()[1]
-            .as_ref()
-            .data
-            .as_ref()
-            .unwrap()[..]
-        {
-            "category" => {
-                let p1 = &var[&inst.params.as_ref().unwrap()[0].as_ref().id];
-                let array = &p1.data.unwrap_array();
+        let name = inst.data.as_ref().unwrap();
+        let what = inst.params.as_ref().unwrap()[0].data.as_ref().unwrap();
 
-                let result: Vec<_> = array
-                    .as_ref()
-                    .iter()
-                    .filter_map(|x| {
-                        let article = x.primitive()?.article()?;
-                        let category_name = &reference.category_index.get(&article.title)?;
+        let option = match &name[..] {
+            "category:exact" => CategoryFindOption::Exact,
+            "category:startswith" => CategoryFindOption::StartsWith,
+            "category:contains" => CategoryFindOption::Contains,
+            _ => unreachable!(),
+        };
 
-                        Some(RuntimeVariableAbstractData::Array(Box::new(
-                            category_name
+        if !reference.category_index.has_members() {
+            return Err(format!(
+                "'{}' needs the category members, build the category index again with `hakurei index category`",
+                name
+            )
+            .into());
+        }
+
+        let articles: Vec<RuntimeVariableAbstractData> = reference
+            .category_index
+            .find_by(what, option)
+            .into_iter()
+            .filter_map(|x| reference.title_index.get_no_redirect(x))
+            .map(|x| {
+                RuntimeVariableAbstractData::Primitive(
+                    RuntimeVariableAbstractPrimitiveData::Article(x),
+                )
+            })
+            .collect();
+
+        Ok(RuntimeVariable {
+            inst,
+            data: RuntimeVariableAbstractData::Array(Box::new(articles)),
+        })
+    }
+
+    fn eval_func_category_descendants<'a>(
+        &self,
+        reference: &RuntimeRef,
+        inst: &'a Instruction,
+    ) -> Result<RuntimeVariable<'a>, Box<dyn Error>> {
+        let params = inst.params.as_ref().unwrap();
+        let what = params[0].data.as_ref().unwrap();
+        let depth: usize = match params.get(1) {
+            Some(param) => param.data.as_ref().unwrap().parse()?,
+            None => CATEGORY_DEPTH_LIMIT,
+        };
+
+        let tree = reference.category_tree.ok_or(
+            "'category:descendants' needs the category tree, build it with `hakurei index category-tree` first",
+        )?;
+
+        let articles: Vec<RuntimeVariableAbstractData> = tree
+            .descendant_articles(what, depth)
+            .into_iter()
+            .filter_map(|x| reference.title_index.get_no_redirect(x))
+            .map(|x| {
+                RuntimeVariableAbstractData::Primitive(
+                    RuntimeVariableAbstractPrimitiveData::Article(x),
+                )
+            })
+            .collect();
+
+        Ok(RuntimeVariable {
+            inst,
+            data: RuntimeVariableAbstractData::Array(Box::new(articles)),
+        })
+    }
+
+    fn eval_func_rank<'a>(
+        &self,
+        var: &HashMap<usize, RuntimeVariable<'a>>,
+        reference: &RuntimeRef,
+        inst: &'a Instruction,
+    ) -> Result<RuntimeVariable<'a>, Box<dyn Error>> {
+        let index = reference
+            .rank_index
+            .ok_or("'rank' needs the rank index, build it with `hakurei index rank` first")?;
+
+        let array = var[&inst.params.as_ref().unwrap()[0].id]
+            .data
+            .unwrap_array();
+
+        // PageRank is kept as an integer, scaled so that the average article scores 1000
+        let scale = index.len() as f64 * 1000.0;
+        let mut articles: Vec<(i64, RuntimeVariableAbstractData)> = array
+            .iter()
+            .map(|x| {
+                let article = x.unwrap_primitive().unwrap_article();
+                let score = match reference.title_index.id(&article.title) {
+                    Some(id) => (index.score(id) * scale).round() as i64,
+                    None => 0,
+                };
+
+                (score, x.clone())
+            })
+            .collect();
+
+        // stable, equal scores keep the order of the input
+        articles.sort_by_key(|x| Reverse(x.0));
+
+        Ok(RuntimeVariable {
+            inst,
+            data: RuntimeVariableAbstractData::Array(Box::new(
+                articles
+                    .into_iter()
+                    .map(|(score, article)| {
+                        RuntimeVariableAbstractData::Tuple(vec![
+                            article,
+                            RuntimeVariableAbstractData::Primitive(
+                                RuntimeVariableAbstractPrimitiveData::Integer(score),
+                            ),
+                        ])
+                    })
+                    .collect(),
+            )),
+        })
+    }
+
+    fn eval_func_count<'a>(
+        &self,
+        var: &HashMap<usize, RuntimeVariable<'a>>,
+        inst: &'a Instruction,
+    ) -> Result<RuntimeVariable<'a>, Box<dyn Error>> {
+        let var = var.get(&inst.params.as_ref().unwrap()[0].id).unwrap();
+
+        let result = match &var.data {
+            RuntimeVariableAbstractData::Array(e) => e.len(),
+            RuntimeVariableAbstractData::Set(e) => e.len(),
+            _ => unreachable!(),
+        };
+
+        Ok(RuntimeVariable {
+            inst,
+            data: RuntimeVariableAbstractData::Primitive(
+                RuntimeVariableAbstractPrimitiveData::Integer(result as i64),
+            ),
+        })
+    }
+
+    // transform array to set
+    fn eval_func_set<'a>(
+        &self,
+        var: &HashMap<usize, RuntimeVariable<'a>>,
+        inst: &'a Instruction,
+    ) -> Result<RuntimeVariable<'a>, Box<dyn Error>> {
+        let mut set: HashSet<&RuntimeVariableAbstractData> = HashSet::new();
+        let mut result: Vec<RuntimeVariableAbstractData> = Vec::new();
+
+        let func_id = &inst.params.as_ref().unwrap()[0].id;
+        let array = &var[func_id].data.unwrap_array();
+
+        array.iter().for_each(|x| {
+            if !set.contains(x) {
+                set.insert(x);
+                // todo: this code maybe mem copy, mem allocation overhead
+                // we must consume target runtime variable, if that variable
+                // unused anymore
+                result.push(x.clone());
+            }
+        });
+
+        Ok(RuntimeVariable {
+            inst,
+            data: RuntimeVariableAbstractData::Set(Box::new(result)),
+        })
+    }
+
+    fn eval_func_group_sum<'a>(
+        &self,
+        var: &HashMap<usize, RuntimeVariable<'a>>,
+        inst: &'a Instruction,
+    ) -> Result<RuntimeVariable<'a>, Box<dyn Error>> {
+        let mut group_map_index: HashMap<&RuntimeVariableAbstractData, usize> = HashMap::new();
+        let mut group_map_count: HashMap<usize, usize> = HashMap::new();
+
+        let func_id = &inst.params.as_ref().unwrap()[0].id;
+        let array = &var[func_id].data.unwrap_array();
+
+        for (i, e) in array.iter().enumerate() {
+            let index = group_map_index.entry(e).or_insert(i);
+            *group_map_count.entry(*index).or_default() += 1;
+        }
+
+        let mut result: Vec<RuntimeVariableAbstractData> = Vec::new();
+
+        for (i, e) in array.iter().enumerate() {
+            if group_map_index[e] == i {
+                let tuple_left = e.clone();
+                let tuple_right = RuntimeVariableAbstractData::Primitive(
+                    RuntimeVariableAbstractPrimitiveData::Integer(group_map_count[&i] as i64),
+                );
+
+                result.push(RuntimeVariableAbstractData::Tuple(vec![
+                    tuple_left,
+                    tuple_right,
+                ]));
+            }
+        }
+
+        result.sort_by(|x, y| {
+            let xx = x.unwrap_tuple()[1].unwrap_primitive().unwrap_integer();
+            let yy = y.unwrap_tuple()[1].unwrap_primitive().unwrap_integer();
+
+            yy.cmp(&xx)
+        });
+
+        Ok(RuntimeVariable {
+            inst,
+            data: RuntimeVariableAbstractData::Array(Box::new(result)),
+        })
+    }
+
+    fn eval_func_filter<'a>(
+        &self,
+        var: &HashMap<usize, RuntimeVariable<'a>>,
+        reference: &'a RuntimeRef,
+        inst: &'a Instruction,
+    ) -> Result<RuntimeVariable<'a>, Box<dyn Error>> {
+        let params = inst.params.as_ref().unwrap();
+        let array = var[&params[0].id].data.unwrap_array();
+        let predicate = &params[1];
+
+        let mut result: Vec<RuntimeVariableAbstractData> = Vec::new();
+
+        if predicate.inst_type == InstructionType::Lambda {
+            let lambda = Lambda::new(predicate);
+
+            for x in array.iter() {
+                if Self::is_true(&lambda.call(self, var, reference, &[x])?) {
+                    result.push(x.clone());
+                }
+            }
+        } else {
+            let consts = predicate.params.as_deref().unwrap_or_default();
+            let name = predicate.data.as_ref().unwrap();
+
+            for x in array.iter() {
+                if Self::is_true(&Self::apply_func_use(reference, name, consts, &[x])?) {
+                    result.push(x.clone());
+                }
+            }
+        }
+
+        Ok(RuntimeVariable {
+            inst,
+            data: RuntimeVariableAbstractData::Array(Box::new(result)),
+        })
+    }
+
+    // Elements a predicate yields nothing for are dropped, see `eval_inst`.
+    fn is_true(x: &RuntimeVariableAbstractData) -> bool {
+        match x {
+            RuntimeVariableAbstractData::None => false,
+            x => *x.unwrap_primitive().unwrap_boolean(),
+        }
+    }
+
+    fn eval_predicate(
+        reference: &RuntimeRef,
+        name: &str,
+        consts: &[Box<Instruction>],
+        x: &RuntimeVariableAbstractData,
+    ) -> Result<bool, Box<dyn Error>> {
+        // safe unwrap thank to semantic analysis
+        let article = x.unwrap_primitive().unwrap_article();
+        let arg = || consts[0].data.as_ref().unwrap();
+
+        Ok(match name {
+            "is_redirect" => article.is_redirect(),
+            "has_category" => reference
+                .category_index
+                .get(&article.title)
+                .is_some_and(|x| x.contains(arg())),
+            "body_contains" => strip_namumark(&article.text).contains(&arg()[..]),
+            "title_len_gt" => article.name().chars().count() > arg().parse()?,
+            "title_len_lt" => article.name().chars().count() < arg().parse()?,
+            _ => unreachable!(),
+        })
+    }
+
+    // `category(x)`, a use function applied to the value of `x` in a lambda.
+    fn eval_func_apply<'a>(
+        &self,
+        var: &HashMap<usize, RuntimeVariable<'a>>,
+        reference: &'a RuntimeRef,
+        inst: &'a Instruction,
+    ) -> Result<RuntimeVariable<'a>, Box<dyn Error>> {
+        let name = inst.data.as_ref().unwrap();
+        let params = inst.params.as_ref().unwrap();
+        let count = params
+            .iter()
+            .take_while(|x| x.inst_type != InstructionType::Constant)
+            .count();
+        let values: Vec<_> = params[..count].iter().map(|x| &var[&x.id].data).collect();
+
+        Ok(RuntimeVariable {
+            inst,
+            data: Self::apply_func_use(reference, name, &params[count..], &values)?,
+        })
+    }
+
+    // Result of the use function `name` with its constants `consts` applied to `values`.
+    fn apply_func_use<'a>(
+        reference: &'a RuntimeRef,
+        name: &str,
+        consts: &[Box<Instruction>],
+        values: &[&RuntimeVariableAbstractData<'a>],
+    ) -> Result<RuntimeVariableAbstractData<'a>, Box<dyn Error>> {
+        let x = values[0];
+
+        if let RuntimeVariableAbstractData::None = x {
+            return Ok(RuntimeVariableAbstractData::None);
+        }
+
+        Ok(match name {
+            "category" => {
+                let article = x.unwrap_primitive().unwrap_article();
+                let categories = reference.category_index.get(&article.title);
+
+                RuntimeVariableAbstractData::Array(Box::new(
+                    categories
+                        .into_iter()
+                        .flatten()
+                        .map(|x| {
+                            RuntimeVariableAbstractData::Primitive(
+                                RuntimeVariableAbstractPrimitiveData::Category(x),
+                            )
+                        })
+                        .collect(),
+                ))
+            }
+            "redirect" => {
+                let article = x.unwrap_primitive().unwrap_article();
+                let article = if article.is_redirect() {
+                    reference
+                        .title_index
+                        .get(&article.title)
+                        .unwrap_or(article.clone())
+                } else {
+                    article.clone()
+                };
+
+                RuntimeVariableAbstractData::Primitive(
+                    RuntimeVariableAbstractPrimitiveData::Article(article),
+                )
+            }
+            "outlinks" | "backlinks" => {
+                let article = x.unwrap_primitive().unwrap_article();
+
+                Self::eval_links(reference, name, article)?
+            }
+            "select_max_len" | "select_min_len" => {
+                let array = match x {
+                    RuntimeVariableAbstractData::Set(e) => e,
+                    e => e.unwrap_array(),
+                };
+
+                // the first of the longest or shortest elements, nothing from an empty array which
+                // `map` drops
+                let selected = if name == "select_max_len" {
+                    array
+                        .iter()
+                        .enumerate()
+                        .max_by_key(|(i, x)| (Self::len(x), Reverse(*i)))
+                        .map(|(_, x)| x)
+                } else {
+                    array.iter().min_by_key(|x| Self::len(x))
+                };
+
+                selected
+                    .cloned()
+                    .unwrap_or(RuntimeVariableAbstractData::None)
+            }
+            "unwrap_tuple1" => x.unwrap_tuple()[0].clone(),
+            "unwrap_tuple2" => x.unwrap_tuple()[1].clone(),
+            "cmp_array" | "cmp_tuple1" | "cmp_tuple2" => {
+                let (x, y) = match name {
+                    "cmp_tuple1" => (&x.unwrap_tuple()[0], &values[1].unwrap_tuple()[0]),
+                    "cmp_tuple2" => (&x.unwrap_tuple()[1], &values[1].unwrap_tuple()[1]),
+                    _ => (x, values[1]),
+                };
+
+                RuntimeVariableAbstractData::Primitive(
+                    RuntimeVariableAbstractPrimitiveData::Integer(Self::compare(x, y)? as i64),
+                )
+            }
+            // the parameters of `bind` are the functions it composes
+            "bind" => {
+                let mut x = x.clone();
+
+                for func in consts {
+                    x = Self::apply_func_use(
+                        reference,
+                        func.data.as_ref().unwrap(),
+                        func.params.as_deref().unwrap_or_default(),
+                        &[&x],
+                    )?;
+                }
+
+                x
+            }
+            "is_redirect" | "has_category" | "body_contains" | "title_len_gt" | "title_len_lt" => {
+                RuntimeVariableAbstractData::Primitive(
+                    RuntimeVariableAbstractPrimitiveData::Boolean(Self::eval_predicate(
+                        reference, name, consts, x,
+                    )?),
+                )
+            }
+            _ => return Err(format!("'{}' cannot be applied yet", name).into()),
+        })
+    }
+
+    // Length `select_*` compares: characters of titles without the namespace, of category names
+    // and of strings, digits of integers and the number of elements of the others.
+    fn len(x: &RuntimeVariableAbstractData) -> usize {
+        match x {
+            RuntimeVariableAbstractData::None => 0,
+            RuntimeVariableAbstractData::Primitive(e) => match e {
+                RuntimeVariableAbstractPrimitiveData::Article(e) => e.name().chars().count(),
+                RuntimeVariableAbstractPrimitiveData::Category(e) => e.chars().count(),
+                RuntimeVariableAbstractPrimitiveData::Integer(e) => e.to_string().len(),
+                RuntimeVariableAbstractPrimitiveData::String(e) => e.chars().count(),
+                RuntimeVariableAbstractPrimitiveData::Boolean(_) => 1,
+            },
+            RuntimeVariableAbstractData::Array(e) | RuntimeVariableAbstractData::Set(e) => e.len(),
+            RuntimeVariableAbstractData::Tuple(e) => e.len(),
+        }
+    }
+
+    // Stable sort with a user comparator, which may fail or not be a total order. Stops at the
+    // first error, `slice::sort_by` panics on some inconsistent orders instead.
+    fn merge_sort<T>(
+        mut array: Vec<T>,
+        compare: &mut impl FnMut(&T, &T) -> Result<Ordering, Box<dyn Error>>,
+    ) -> Result<Vec<T>, Box<dyn Error>> {
+        if array.len() <= 1 {
+            return Ok(array);
+        }
+
+        let right = array.split_off(array.len() / 2);
+        let mut left = Self::merge_sort(array, compare)?.into_iter().peekable();
+        let mut right = Self::merge_sort(right, compare)?.into_iter().peekable();
+        let mut result = Vec::with_capacity(left.len() + right.len());
+
+        while let (Some(x), Some(y)) = (left.peek(), right.peek()) {
+            // ties keep their order
+            if compare(y, x)? == Ordering::Less {
+                result.push(right.next().unwrap());
+            } else {
+                result.push(left.next().unwrap());
+            }
+        }
+
+        result.extend(left);
+        result.extend(right);
+
+        Ok(result)
+    }
+
+    // Natural order of values: articles by title, tuples, arrays and sets field by field.
+    fn compare(
+        x: &RuntimeVariableAbstractData,
+        y: &RuntimeVariableAbstractData,
+    ) -> Result<Ordering, Box<dyn Error>> {
+        Ok(match (x, y) {
+            (
+                RuntimeVariableAbstractData::Primitive(x),
+                RuntimeVariableAbstractData::Primitive(y),
+            ) => match (x, y) {
+                (
+                    RuntimeVariableAbstractPrimitiveData::Article(x),
+                    RuntimeVariableAbstractPrimitiveData::Article(y),
+                ) => x.title.cmp(&y.title),
+                (
+                    RuntimeVariableAbstractPrimitiveData::Category(x),
+                    RuntimeVariableAbstractPrimitiveData::Category(y),
+                ) => x.cmp(y),
+                (
+                    RuntimeVariableAbstractPrimitiveData::Integer(x),
+                    RuntimeVariableAbstractPrimitiveData::Integer(y),
+                ) => x.cmp(y),
+                (
+                    RuntimeVariableAbstractPrimitiveData::String(x),
+                    RuntimeVariableAbstractPrimitiveData::String(y),
+                ) => x.cmp(y),
+                (
+                    RuntimeVariableAbstractPrimitiveData::Boolean(x),
+                    RuntimeVariableAbstractPrimitiveData::Boolean(y),
+                ) => x.cmp(y),
+                _ => return Err("Values of different types cannot be compared".into()),
+            },
+            (RuntimeVariableAbstractData::Array(x), RuntimeVariableAbstractData::Array(y))
+            | (RuntimeVariableAbstractData::Set(x), RuntimeVariableAbstractData::Set(y)) => {
+                Self::compare_fields(x, y)?
+            }
+            // `select_*` of an empty array
+            (RuntimeVariableAbstractData::None, RuntimeVariableAbstractData::None) => {
+                Ordering::Equal
+            }
+            (RuntimeVariableAbstractData::None, _) => Ordering::Less,
+            (_, RuntimeVariableAbstractData::None) => Ordering::Greater,
+            (RuntimeVariableAbstractData::Tuple(x), RuntimeVariableAbstractData::Tuple(y)) => {
+                Self::compare_fields(x, y)?
+            }
+            _ => return Err("Values of different types cannot be compared".into()),
+        })
+    }
+
+    fn compare_fields(
+        x: &[RuntimeVariableAbstractData],
+        y: &[RuntimeVariableAbstractData],
+    ) -> Result<Ordering, Box<dyn Error>> {
+        for (x, y) in x.iter().zip(y) {
+            let order = Self::compare(x, y)?;
+
+            if order.is_ne() {
+                return Ok(order);
+            }
+        }
+
+        Ok(x.len().cmp(&y.len()))
+    }
+
+    // `sort(arr, cmp_array)` orders by a comparator returning a negative, zero or positive
+    // integer, `sort_by(arr, unwrap_tuple2)` by the keys a function maps the elements to. Both
+    // are stable, `:desc` reverses the order.
+    fn eval_func_sort<'a>(
+        &self,
+        var: &HashMap<usize, RuntimeVariable<'a>>,
+        reference: &'a RuntimeRef,
+        inst: &'a Instruction,
+    ) -> Result<RuntimeVariable<'a>, Box<dyn Error>> {
+        let name = inst.data.as_ref().unwrap();
+        let params = inst.params.as_ref().unwrap();
+        let mut array = var[&params[0].id].data.unwrap_array().to_vec();
+
+        let func = &params[1];
+        let lambda = (func.inst_type == InstructionType::Lambda).then(|| Lambda::new(func));
+        let consts = func.params.as_deref().unwrap_or_default();
+        let apply = |values: &[&RuntimeVariableAbstractData<'a>]| match &lambda {
+            Some(lambda) => lambda.call(self, var, reference, values),
+            None => Self::apply_func_use(reference, func.data.as_ref().unwrap(), consts, values),
+        };
+
+        let desc = name.ends_with(":desc");
+        let order = |x: Ordering| if desc { x.reverse() } else { x };
+
+        if name.starts_with("sort_by") {
+            let mut keyed = Vec::new();
+
+            for x in array {
+                keyed.push((apply(&[&x])?, x));
+            }
+
+            keyed = Self::merge_sort(keyed, &mut |(x, _), (y, _)| Ok(order(Self::compare(x, y)?)))?;
+            array = keyed.into_iter().map(|(_, x)| x).collect();
+        } else {
+            array = Self::merge_sort(array, &mut |x, y| {
+                Ok(match apply(&[x, y])? {
+                    RuntimeVariableAbstractData::None => Ordering::Equal,
+                    e => order(e.unwrap_primitive().unwrap_integer().cmp(&0)),
+                })
+            })?;
+        }
+
+        Ok(RuntimeVariable {
+            inst,
+            data: RuntimeVariableAbstractData::Array(Box::new(array)),
+        })
+    }
+
+    // Distinct articles `article` links to or is linked from, none if it is not in the link
+    // index.
+    fn eval_links<'a>(
+        reference: &'a RuntimeRef,
+        name: &str,
+        article: &Article,
+    ) -> Result<RuntimeVariableAbstractData<'a>, Box<dyn Error>> {
+        let index = reference.link_index.ok_or(format!(
+            "'{}' needs the link index, build it with `hakurei index link` first",
+            name
+        ))?;
+
+        let id = match reference.title_index.id(&article.title) {
+            Some(id) => id,
+            None => return Ok(RuntimeVariableAbstractData::Array(Box::default())),
+        };
+        let ids = if name == "outlinks" {
+            index.outlinks(id)
+        } else {
+            index.backlinks(id)
+        };
+
+        // edges point to resolved articles
+        Ok(RuntimeVariableAbstractData::Array(Box::new(
+            ids.into_iter()
+                .filter_map(|id| {
+                    reference
+                        .title_index
+                        .get_no_redirect(reference.title_index.title(id as usize))
+                })
+                .map(|x| {
+                    RuntimeVariableAbstractData::Primitive(
+                        RuntimeVariableAbstractPrimitiveData::Article(x),
+                    )
+                })
+                .collect::<Vec<_>>(),
+        )))
+    }
+
+    fn eval_func_map<'a>(
+        &self,
+        var: &HashMap<usize, RuntimeVariable<'a>>,
+        reference: &'a RuntimeRef,
+        inst: &'a Instruction,
+    ) -> Result<RuntimeVariable<'a>, Box<dyn Error>> {
+        let func = &inst.params.as_ref().unwrap()[1];
+
+        if func.inst_type == InstructionType::Lambda {
+            let lambda = Lambda::new(func);
+            let p1 = &var[&inst.params.as_ref().unwrap()[0].as_ref().id];
+
+            let mut result: Vec<_> = Vec::new();
+
+            // elements mapped to nothing are dropped, see `eval_inst`
+            for x in p1.data.unwrap_array().iter() {
+                match lambda.call(self, var, reference, &[x])? {
+                    RuntimeVariableAbstractData::None => {}
+                    e => result.push(e),
+                }
+            }
+
+            return Ok(RuntimeVariable {
+                inst,
+                data: RuntimeVariableAbstractData::Array(Box::new(result)),
+            });
+        }
+
+        match &func.data.as_ref().unwrap()[..] {
+            "category" => {
+                let p1 = &var[&inst.params.as_ref().unwrap()[0].as_ref().id];
+                let array = &p1.data.unwrap_array();
+
+                let result: Vec<_> = array
+                    .as_ref()
+                    .iter()
+                    .filter_map(|x| {
+                        let article = x.primitive()?.article()?;
+                        let category_name = &reference.category_index.get(&article.title)?;
+
+                        Some(RuntimeVariableAbstractData::Array(Box::new(
+                            category_name
                                 .into_iter()
                                 .map(|x| {
                                     RuntimeVariableAbstractData::Primitive(
@@ -422,7 +1553,6 @@ impl VirtualMachine<'_> {
                     data: RuntimeVariableAbstractData::Array(Box::new(result)),
                 })
             }
-            "select_min_len" | "select_max_len" => todo!(),
             "redirect" => {
                 let p1 = &var[&inst.params.as_ref().unwrap()[0].as_ref().id];
                 let array = &p1.data.unwrap_array();
@@ -452,8 +1582,30 @@ impl VirtualMachine<'_> {
                     data: RuntimeVariableAbstractData::Array(Box::new(result)),
                 })
             }
-            "sort" => todo!(),
-            _ => unreachable!(),
+            // `outlinks`, `select_max_len`, `unwrap_tuple2`, `bind(redirect, category)`
+            name => {
+                let func = &inst.params.as_ref().unwrap()[1];
+                let p1 = &var[&inst.params.as_ref().unwrap()[0].as_ref().id];
+
+                let mut result: Vec<_> = Vec::new();
+
+                for x in p1.data.unwrap_array().iter() {
+                    match Self::apply_func_use(
+                        reference,
+                        name,
+                        func.params.as_deref().unwrap_or_default(),
+                        &[x],
+                    )? {
+                        RuntimeVariableAbstractData::None => {}
+                        e => result.push(e),
+                    }
+                }
+
+                Ok(RuntimeVariable {
+                    inst,
+                    data: RuntimeVariableAbstractData::Array(Box::new(result)),
+                })
+            }
         }
     }
 
@@ -487,9 +1639,16 @@ mod tests {
     use crate::{
         core::{
             ir::IRBuilder,
-            vm::{RuntimeVariable, RuntimeVariableAbstractPrimitiveData},
+            vm::{Lambda, RuntimeVariable, RuntimeVariableAbstractPrimitiveData},
+        },
+        index::{
+            body::BodyIndex, category::CategoryIndex, category_tree::CategoryTree, link::LinkIndex,
+            rank::RankIndex, redirect::RedirectTable, title::TitleIndex,
+        },
+        loader::{
+            fixture::{temp_path, write_namespaced_dump},
+            load::load_dump,
         },
-        index::{category::CategoryIndex, title::TitleIndex},
         DEFAULT_CATEGORY_INDEX_PATH, DEFAULT_DUMP_PATH, DEFAULT_TITLE_INDEX_PATH,
     };
 
@@ -509,7 +1668,11 @@ mod tests {
 
             let rt_ref = RuntimeRef {
                 category_index: &cindex,
+                category_tree: None,
                 title_index: &tindex,
+                body_index: None,
+                link_index: None,
+                rank_index: None,
                 articles: None,
             };
 
@@ -561,6 +1724,756 @@ mod tests {
         );
     }
 
+    #[test]
+    fn vm_body_test() {
+        let articles = [
+            (
+                "동방프로젝트",
+                "== 개요 ==\n탄막 슈팅 게임\n== 작품 목록 ==",
+            ),
+            ("하쿠레이 레이무", "== 개요 ==\n하쿠레이 신사의 무녀"),
+            ("레이무", "#redirect 하쿠레이 레이무"),
+            (
+                "서든어택",
+                "=== 작품 목록 ===\n슈팅 게임 [[하쿠레이 레이무|레이무]]와 친구",
+            ),
+        ];
+
+        for fixture in [
+            Fixture::new("vm-body-stream.json", &articles),
+            Fixture::new("vm-body-loaded.json", &articles).with_loaded_articles(),
+            Fixture::new("vm-body-index.json", &articles).with_body_index(),
+        ] {
+            assert_eq!(
+                fixture.run("body:contains(\"하쿠레이\")"),
+                vec!["하쿠레이 레이무"]
+            );
+            assert_eq!(
+                fixture.run("body:contains(\"슈팅 게임\")"),
+                vec!["동방프로젝트", "서든어택"]
+            );
+            assert_eq!(
+                fixture.run("body:contains(\"막 슈\")"),
+                vec!["동방프로젝트"]
+            );
+            assert!(fixture.run("body:contains(\"게임 슈팅\")").is_empty());
+            assert_eq!(
+                fixture.run("body:regex(\"(탄막 )?슈팅\")"),
+                vec!["동방프로젝트", "서든어택"]
+            );
+            assert_eq!(
+                fixture.run("body:menu_exists(\"작품 목록\")"),
+                vec!["동방프로젝트", "서든어택"]
+            );
+            assert!(fixture.run("body:menu_exists(\"작품\")").is_empty());
+            // link targets are not part of the rendered text
+            assert_eq!(
+                fixture.run("body:contains(\"레이무와 친구\")"),
+                vec!["서든어택"]
+            );
+            assert!(fixture
+                .run("body:contains(\"하쿠레이 레이무|\")")
+                .is_empty());
+        }
+    }
+
+    #[test]
+    fn vm_body_search_test() {
+        let articles = [
+            ("동방프로젝트", "탄막 슈팅 게임"),
+            ("하쿠레이 레이무", "동방프로젝트의 주인공"),
+            ("서든어택", "슈팅 게임"),
+        ];
+        let fixture = Fixture::new("vm-body-search.json", &articles).with_body_index();
+
+        assert_eq!(
+            fixture.run("body:search(\"동방프로젝트\")"),
+            vec!["동방프로젝트", "하쿠레이 레이무"]
+        );
+        assert_eq!(
+            fixture.run("body:search(\"슈팅 게임\", 1)"),
+            vec!["서든어택"]
+        );
+    }
+
+    #[test]
+    fn vm_link_test() {
+        let articles = [
+            (
+                "동방프로젝트",
+                "[[하쿠레이 레이무|레이무]]와 [[키리사메 마리사#개요]]. [[레이무]]",
+            ),
+            ("레이무", "#redirect 하쿠레이 레이무"),
+            ("하쿠레이 레이무", "[[동방프로젝트]]의 주인공."),
+            ("키리사메 마리사", "[[하쿠레이 레이무]]와 친하다."),
+        ];
+        let fixture = Fixture::new("vm-link.json", &articles).with_link_index();
+
+        assert_eq!(
+            fixture.run("flatten(map(title:exact(\"동방프로젝트\"), outlinks))"),
+            vec!["하쿠레이 레이무", "키리사메 마리사"]
+        );
+        assert_eq!(
+            fixture.run("flatten(map(title:exact(\"하쿠레이 레이무\"), backlinks))"),
+            vec!["동방프로젝트", "키리사메 마리사"]
+        );
+        assert!(fixture
+            .run("flatten(map(title:exact(\"레이무\"), outlinks))")
+            .is_empty());
+        // one element per article, linking nowhere or not
+        assert_eq!(
+            fixture.run("map(map(title:contains(\"레이무\"), outlinks), x => count(x))"),
+            vec!["0", "1"]
+        );
+    }
+
+    #[test]
+    fn vm_links_to_test() {
+        let articles = [
+            ("동방프로젝트", "[[하쿠레이 레이무]]"),
+            ("레이무", "#redirect 하쿠레이 레이무"),
+            ("하쿠레이 레이무", "[[동방프로젝트]]의 주인공."),
+            (
+                "키리사메 마리사",
+                "[[하쿠레이 레이무]]와 친하다. [[레이무]]와 [[하쿠레이 레이무#관계|같이]]",
+            ),
+        ];
+        let fixture = Fixture::new("vm-links-to.json", &articles).with_link_index();
+
+        assert_eq!(
+            fixture.run("links_to(\"레이무\")"),
+            vec![
+                "동방프로젝트",
+                "키리사메 마리사",
+                "키리사메 마리사",
+                "키리사메 마리사"
+            ]
+        );
+        // most linking articles first
+        assert_eq!(
+            fixture.run("group_sum(links_to(\"하쿠레이 레이무\"))"),
+            vec!["키리사메 마리사", "동방프로젝트"]
+        );
+        assert!(fixture.run("links_to(\"없는 문서\")").is_empty());
+    }
+
+    #[test]
+    fn vm_redirects_to_test() {
+        let articles = [
+            ("하쿠레이 레이무", "[[동방프로젝트]]의 주인공."),
+            ("레이무", "#redirect 하쿠레이 레이무"),
+            ("영몽", "#넘겨주기 레이무"),
+            ("A", "#redirect B"),
+            ("B", "#redirect A"),
+        ];
+        let fixture = Fixture::new("vm-redirects-to.json", &articles).with_redirect_table();
+
+        assert_eq!(
+            fixture.run("redirects_to(\"하쿠레이 레이무\")"),
+            vec!["레이무", "영몽"]
+        );
+        assert_eq!(
+            fixture.run("redirects_to(\"영몽\")"),
+            vec!["레이무", "영몽"]
+        );
+        assert!(fixture.run("redirects_to(\"A\")").is_empty());
+        assert!(fixture.run("redirects_to(\"없는 문서\")").is_empty());
+    }
+
+    #[test]
+    fn vm_rank_test() {
+        let articles = [
+            ("동방프로젝트", "[[하쿠레이 레이무]] [[키리사메 마리사]]"),
+            ("하쿠레이 레이무", "[[동방프로젝트]]"),
+            ("키리사메 마리사", "[[하쿠레이 레이무]]"),
+            ("동방 신사", "[[하쿠레이 레이무]]"),
+        ];
+        let fixture = Fixture::new("vm-rank.json", &articles).with_rank_index();
+
+        assert_eq!(
+            fixture.run("rank(title:contains(\"\"))"),
+            vec![
+                "하쿠레이 레이무",
+                "동방프로젝트",
+                "키리사메 마리사",
+                "동방 신사"
+            ]
+        );
+        assert_eq!(
+            fixture.run("rank(title:startswith(\"동방\"))"),
+            vec!["동방프로젝트", "동방 신사"]
+        );
+    }
+
+    #[test]
+    fn vm_path_test() {
+        let articles = [
+            ("동방프로젝트", "[[하쿠레이 레이무]]"),
+            ("레이무", "#redirect 하쿠레이 레이무"),
+            ("하쿠레이 레이무", "[[하쿠레이 신사]]"),
+            ("하쿠레이 신사", "[[동방프로젝트]]"),
+            ("서든어택", ""),
+        ];
+        let fixture = Fixture::new("vm-path.json", &articles).with_link_index();
+
+        assert_eq!(
+            fixture.run("path(\"동방프로젝트\", \"하쿠레이 신사\")"),
+            vec!["동방프로젝트", "하쿠레이 레이무", "하쿠레이 신사"]
+        );
+        assert_eq!(
+            fixture.run("path(\"레이무\", \"동방프로젝트\", 2)"),
+            vec!["하쿠레이 레이무", "하쿠레이 신사", "동방프로젝트"]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "No path from")]
+    fn vm_path_not_found_test() {
+        let articles = [("동방프로젝트", "[[서든어택]]"), ("서든어택", "")];
+
+        Fixture::new("vm-path-not-found.json", &articles)
+            .with_link_index()
+            .run("path(\"서든어택\", \"동방프로젝트\")");
+    }
+
+    #[test]
+    fn vm_category_test() {
+        let articles = [
+            (
+                "너의 이름은.",
+                "[[분류:일본 애니메이션/2016년]][[분류:신카이 마코토]]",
+            ),
+            (
+                "날씨의 아이",
+                "[[분류:일본 애니메이션/2019년]][[분류:신카이 마코토]]",
+            ),
+            ("하쿠레이 레이무", "[[분류:동방 프로젝트/등장인물]]"),
+        ];
+        let fixture = Fixture::new("vm-category.json", &articles);
+
+        assert_eq!(
+            fixture.run("category:startswith(\"일본 애니메이션/\")"),
+            vec!["날씨의 아이", "너의 이름은."]
+        );
+        assert_eq!(
+            fixture.run("category:exact(\"일본 애니메이션/2016년\")"),
+            vec!["너의 이름은."]
+        );
+        assert_eq!(
+            fixture
+                .run("category:contains(\"등장인물\") | category:exact(\"신카이 마코토\")")
+                .len(),
+            3
+        );
+    }
+
+    #[test]
+    fn vm_category_descendants_test() {
+        let articles = [
+            (0, "하쿠레이 레이무", "[[분류:동방 프로젝트/등장인물]]"),
+            (0, "하쿠레이 신사", "[[분류:동방 프로젝트]]"),
+            (0, "서든어택", "[[분류:게임]]"),
+            (2, "동방 프로젝트", "[[분류:게임]]"),
+            (2, "동방 프로젝트/등장인물", "[[분류:동방 프로젝트]]"),
+        ];
+        let fixture =
+            Fixture::namespaced("vm-category-descendants.json", &articles).with_category_tree();
+
+        assert_eq!(
+            fixture.run("category:descendants(\"동방 프로젝트\")"),
+            vec!["하쿠레이 레이무", "하쿠레이 신사"]
+        );
+        assert_eq!(
+            fixture.run("category:descendants(\"게임\", 1)"),
+            vec!["서든어택", "하쿠레이 신사"]
+        );
+        assert_eq!(
+            fixture.run("category:descendants(\"게임\", 0)"),
+            vec!["서든어택"]
+        );
+    }
+
+    #[test]
+    fn vm_namespace_test() {
+        let articles = [
+            (0, "동방 프로젝트", "[[분류:게임]]"),
+            (1, "동방 프로젝트", "[[분류:틀]]"),
+            (2, "동방 프로젝트", "[[분류:게임]]"),
+            (0, "동방 신사", "#redirect 동방 프로젝트"),
+        ];
+        let fixture = Fixture::namespaced("vm-namespace.json", &articles).with_rank_index();
+
+        assert_eq!(
+            fixture.run("title:contains(\"동방\")"),
+            vec![
+                "동방 신사",
+                "동방 프로젝트",
+                "분류:동방 프로젝트",
+                "틀:동방 프로젝트"
+            ]
+        );
+        assert_eq!(
+            fixture.run("title:contains(\"동방\", ns=\"틀\")"),
+            vec!["틀:동방 프로젝트"]
+        );
+        assert_eq!(
+            fixture.run("title:startswith(\"동방\", ns=\"문서\")"),
+            vec!["동방 신사", "동방 프로젝트"]
+        );
+        assert_eq!(
+            fixture.run("ns:category(title:contains(\"프로젝트\"))"),
+            vec!["분류:동방 프로젝트"]
+        );
+        assert_eq!(
+            fixture.run("rank(title:contains(\"동방\"), ns=\"분류\")"),
+            vec!["분류:동방 프로젝트"]
+        );
+        assert_eq!(
+            fixture.run("category:exact(\"게임\", ns=\"문서\")"),
+            vec!["동방 프로젝트"]
+        );
+    }
+
+    #[test]
+    fn vm_filter_test() {
+        let articles = [
+            ("동방 프로젝트", "[[분류:게임]] 탄막 슈팅"),
+            ("동방", "#redirect 동방 프로젝트"),
+            ("동방 신사", "[[분류:장소]]"),
+            ("동방신기", "[[분류:가수]]"),
+        ];
+        let fixture = Fixture::new("vm-filter.json", &articles);
+
+        assert_eq!(
+            fixture.run("filter(title:contains(\"동방\"), is_redirect)"),
+            vec!["동방"]
+        );
+        assert_eq!(
+            fixture.run("filter(title:contains(\"동방\"), has_category(\"게임\"))"),
+            vec!["동방 프로젝트"]
+        );
+        assert_eq!(
+            fixture.run("filter(title:startswith(\"동방\"), body_contains(\"탄막\"))"),
+            vec!["동방 프로젝트"]
+        );
+        assert_eq!(
+            fixture.run("filter(title:startswith(\"동방\"), title_len_gt(5))"),
+            vec!["동방 프로젝트"]
+        );
+        assert_eq!(
+            fixture.run(
+                "filter(filter(title:startswith(\"동방\"), title_len_lt(5)), title_len_gt(2))"
+            ),
+            vec!["동방신기"]
+        );
+    }
+
+    #[test]
+    fn vm_lambda_test() {
+        let articles = [
+            ("동방 프로젝트", "[[분류:게임]] 탄막 슈팅"),
+            ("동방", "#redirect 동방 프로젝트"),
+            ("동방 신사", "[[분류:장소]]"),
+            ("동방신기", "[[분류:가수]] [[분류:그룹]]"),
+        ];
+        let fixture = Fixture::new("vm-lambda.json", &articles);
+
+        assert_eq!(
+            fixture.run("filter(title:contains(\"동방\"), x => has_category(x, \"게임\"))"),
+            vec!["동방 프로젝트"]
+        );
+        assert_eq!(
+            fixture
+                .run("filter(title:contains(\"동방\"), x => has_category(redirect(x), \"게임\"))"),
+            vec!["동방", "동방 프로젝트"]
+        );
+        assert_eq!(
+            fixture.run("map(title:startswith(\"동방\"), x => count(category(x)))"),
+            vec!["0", "1", "1", "2"]
+        );
+        assert_eq!(
+            fixture.run("map(title:contains(\"동방\"), x => is_redirect(x))"),
+            vec!["true", "false", "false", "false"]
+        );
+        // `|` and `&` between booleans are a logical or and and
+        assert_eq!(
+            fixture.run(
+                "filter(title:contains(\"동방\"), x => is_redirect(x) | has_category(x, \"게임\"))"
+            ),
+            vec!["동방", "동방 프로젝트"]
+        );
+        assert_eq!(
+            fixture.run(
+                "filter(title:contains(\"동방\"), \
+                 x => has_category(x, \"가수\") & has_category(x, \"그룹\"))"
+            ),
+            vec!["동방신기"]
+        );
+        // other primitives are concatenated as elements
+        assert_eq!(
+            fixture.run(
+                "map(title:startswith(\"동방\"), \
+                 x => count(select_max_len(category(x)) | category(x)))"
+            ),
+            vec!["2", "2", "3"]
+        );
+        assert_eq!(
+            fixture.run(
+                "map(title:startswith(\"동방\"), \
+                 x => count(set(select_max_len(category(x)) | category(x))))"
+            ),
+            vec!["1", "1", "2"]
+        );
+        // `x` is captured by the inner lambda
+        assert_eq!(
+            fixture.run(
+                "map(title:startswith(\"동방\"), \
+                 x => count(filter(title:contains(\"동방\"), y => has_category(x, \"게임\"))))"
+            ),
+            vec!["0", "0", "4", "0"]
+        );
+
+        // the inner `title:contains` does not use `x` and is evaluated once
+        let head_inst = IRBuilder::from(
+            "map(title:startswith(\"동방\"), \
+             x => count(filter(title:contains(\"동방\"), y => has_category(x, \"게임\"))))",
+        )
+        .unwrap()
+        .build();
+        let lambda = Lambda::new(&head_inst.params.as_ref().unwrap()[1]);
+        let uses_x = |name: &str| {
+            let inst = lambda
+                .insts
+                .iter()
+                .find(|x| x.data.as_deref() == Some(name))
+                .unwrap();
+            lambda.dependent.contains(&inst.id)
+        };
+
+        assert!(!uses_x("title:contains"));
+        assert!(uses_x("filter"));
+        assert!(uses_x("count"));
+    }
+
+    #[test]
+    fn vm_sort_test() {
+        let articles = [
+            ("동방 프로젝트", "[[분류:게임]] 탄막 슈팅"),
+            ("동방", "#redirect 동방 프로젝트"),
+            ("동방 신사", "[[분류:장소]]"),
+            ("동방신기", "[[분류:가수]] [[분류:게임]]"),
+        ];
+        let fixture = Fixture::new("vm-sort.json", &articles);
+
+        assert_eq!(
+            fixture.run("sort(title:contains(\"동방\"), cmp_array)"),
+            vec!["동방", "동방 신사", "동방 프로젝트", "동방신기"]
+        );
+        assert_eq!(
+            fixture.run("sort:desc(title:contains(\"동방\"), cmp_array)"),
+            vec!["동방신기", "동방 프로젝트", "동방 신사", "동방"]
+        );
+        // ties keep their order
+        assert_eq!(
+            fixture.run("sort_by:desc(title:contains(\"동방\"), x => count(category(x)))"),
+            vec!["동방신기", "동방 신사", "동방 프로젝트", "동방"]
+        );
+        assert_eq!(
+            fixture.run("sort_by(title:contains(\"동방\"), is_redirect)"),
+            vec!["동방 신사", "동방 프로젝트", "동방신기", "동방"]
+        );
+        assert_eq!(
+            fixture.run(
+                "sort_by:desc(sort(group_sum(flatten(map(title:contains(\"동방\"), category))), \
+                 cmp_tuple1), unwrap_tuple2)"
+            ),
+            vec!["게임", "가수", "장소"]
+        );
+        assert_eq!(
+            fixture.run(
+                "sort(group_sum(flatten(map(title:contains(\"동방\"), category))), \
+                 (x, y) => cmp_tuple2(y, x))"
+            )[0],
+            "게임"
+        );
+
+        // comparators which are not a total order do not panic
+        assert_eq!(
+            fixture
+                .run("sort(title:contains(\"동방\"), (x, y) => count(category(x)))")
+                .len(),
+            4
+        );
+        // the first error of the comparator is returned, `outlinks` needs the link index
+        assert!(fixture
+            .try_run("sort(title:contains(\"동방\"), (x, y) => count(outlinks(x)))")
+            .is_err());
+
+        // sets are compared like arrays
+        let articles = [
+            ("A", "[[B]] [[C]]"),
+            ("B", "[[분류:가]] [[분류:나]]"),
+            ("C", "[[분류:가]]"),
+            ("D", "[[C]]"),
+        ];
+        let fixture = Fixture::new("vm-sort-sets.json", &articles).with_link_index();
+
+        assert_eq!(
+            fixture
+                .run("sort_by(title:contains(\"\"), x => map(outlinks(x), y => set(category(y))))"),
+            vec!["B", "C", "D", "A"]
+        );
+        assert_eq!(
+            fixture.run(
+                "map(sort(map(title:contains(\"\"), x => map(outlinks(x), y => set(category(y)))), \
+                 cmp_array), x => count(x))"
+            ),
+            vec!["0", "0", "1", "2"]
+        );
+    }
+
+    #[test]
+    fn vm_bind_test() {
+        let articles = [
+            ("동방 프로젝트", "[[분류:게임]] 탄막 슈팅"),
+            ("동방", "#redirect 동방 프로젝트"),
+            ("동방 신사", "[[분류:장소]]"),
+        ];
+        let fixture = Fixture::new("vm-bind.json", &articles);
+
+        assert_eq!(
+            fixture.run("flatten(map(title:contains(\"동방\"), bind(redirect, category)))"),
+            vec!["게임", "장소", "게임"]
+        );
+        assert_eq!(
+            fixture.run("filter(title:contains(\"동방\"), bind(redirect, has_category(\"게임\")))"),
+            vec!["동방", "동방 프로젝트"]
+        );
+        assert_eq!(
+            fixture.run("sort_by(title:contains(\"동방\"), bind(redirect, is_redirect))"),
+            vec!["동방", "동방 신사", "동방 프로젝트"]
+        );
+    }
+
+    #[test]
+    fn vm_select_unwrap_test() {
+        let articles = [
+            ("동방 프로젝트", "[[분류:게임]] [[분류:슈팅 게임]]"),
+            ("동방 신사", "[[분류:장소]]"),
+            ("동방신기", "[[분류:가수]] [[분류:아이돌]] [[분류:그룹]]"),
+        ];
+        let fixture = Fixture::new("vm-select.json", &articles);
+
+        assert_eq!(
+            fixture.run("map(map(title:contains(\"동방\"), category), select_max_len)"),
+            vec!["장소", "슈팅 게임", "아이돌"]
+        );
+        assert_eq!(
+            fixture.run("map(map(title:contains(\"동방\"), category), select_min_len)"),
+            vec!["장소", "게임", "가수"]
+        );
+        assert_eq!(
+            fixture.run("map(title:contains(\"동방\"), x => select_max_len(category(x)))"),
+            vec!["장소", "슈팅 게임", "아이돌"]
+        );
+        assert_eq!(
+            fixture.run(
+                "map(sort(group_sum(flatten(map(title:startswith(\"동방 \"), category))), \
+                 cmp_tuple1), unwrap_tuple1)"
+            ),
+            vec!["게임", "슈팅 게임", "장소"]
+        );
+        assert_eq!(
+            fixture.run(
+                "map(group_sum(flatten(map(title:contains(\"동방\"), category))), unwrap_tuple2)"
+            ),
+            vec!["1"; 6]
+        );
+
+        // articles without categories or links select nothing and are dropped
+        let articles = [
+            ("동방 프로젝트", "[[분류:게임]] [[동방 신사]]"),
+            ("동방 신사", "탄막"),
+        ];
+        let fixture = Fixture::new("vm-select-empty.json", &articles).with_link_index();
+
+        assert_eq!(
+            fixture.run("map(map(title:contains(\"동방\"), category), select_max_len)"),
+            vec!["게임"]
+        );
+        assert_eq!(
+            fixture.run("map(title:contains(\"동방\"), x => select_min_len(category(x)))"),
+            vec!["게임"]
+        );
+        assert_eq!(
+            fixture.run(
+                "filter(map(map(title:contains(\"\"), outlinks), select_max_len), is_redirect)"
+            ),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            fixture.run("map(map(title:contains(\"\"), outlinks), select_max_len)"),
+            vec!["동방 신사"]
+        );
+        assert_eq!(
+            fixture
+                .run("filter(title:contains(\"\"), x => is_redirect(select_max_len(outlinks(x))))"),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            fixture.run(
+                "map(title:contains(\"\"), x => count(category(select_max_len(outlinks(x)))))"
+            ),
+            vec!["0"]
+        );
+        assert_eq!(
+            fixture.run("sort_by(title:contains(\"\"), x => select_max_len(category(x)))"),
+            vec!["동방 신사", "동방 프로젝트"]
+        );
+    }
+
+    // A fixture dump with the title and category indexes, the other indexes are built only for
+    // the tests querying them.
+    struct Fixture {
+        name: String,
+        dump_path: String,
+        title_index: TitleIndex,
+        category_index: CategoryIndex,
+        category_tree: Option<CategoryTree>,
+        body_index: Option<BodyIndex>,
+        link_index: Option<LinkIndex>,
+        rank_index: Option<RankIndex>,
+        loaded: bool,
+    }
+
+    impl Fixture {
+        fn new(name: &str, articles: &[(&str, &str)]) -> Self {
+            let articles: Vec<_> = articles.iter().map(|(x, y)| (0, *x, *y)).collect();
+
+            Self::namespaced(name, &articles)
+        }
+
+        fn namespaced(name: &str, articles: &[(i64, &str, &str)]) -> Self {
+            let dump_path = write_namespaced_dump(name, articles);
+            let title_index_path = temp_path(&format!("{}-title", name));
+            let category_index_path = temp_path(&format!("{}-category", name));
+
+            TitleIndex::build(&dump_path, &title_index_path).unwrap();
+            CategoryIndex::build(&dump_path, &category_index_path).unwrap();
+
+            Fixture {
+                name: name.to_owned(),
+                title_index: TitleIndex::load(&dump_path, &title_index_path).unwrap(),
+                category_index: CategoryIndex::load(&category_index_path).unwrap(),
+                dump_path,
+                category_tree: None,
+                body_index: None,
+                link_index: None,
+                rank_index: None,
+                loaded: false,
+            }
+        }
+
+        fn with_redirect_table(mut self) -> Self {
+            let path = temp_path(&format!("{}-redirect", self.name));
+
+            RedirectTable::build(&self.title_index, &self.dump_path, &path).unwrap();
+            let table = RedirectTable::open(&path, &self.title_index).unwrap();
+            self.title_index.set_redirect_table(table);
+
+            self
+        }
+
+        fn with_category_tree(mut self) -> Self {
+            let path = temp_path(&format!("{}-category-tree", self.name));
+
+            CategoryTree::build(&self.dump_path, &path).unwrap();
+            self.category_tree = Some(CategoryTree::load(&path).unwrap());
+
+            self
+        }
+
+        fn with_body_index(mut self) -> Self {
+            let path = temp_path(&format!("{}-body", self.name));
+
+            BodyIndex::build(&self.dump_path, &path).unwrap();
+            self.body_index = Some(BodyIndex::open(&self.dump_path, &path).unwrap());
+
+            self
+        }
+
+        fn with_link_index(mut self) -> Self {
+            let path = temp_path(&format!("{}-link", self.name));
+
+            LinkIndex::build(&self.title_index, &self.dump_path, &path).unwrap();
+            self.link_index = Some(LinkIndex::open(&path, &self.title_index).unwrap());
+
+            self
+        }
+
+        fn with_rank_index(self) -> Self {
+            let mut fixture = self.with_link_index();
+            let path = temp_path(&format!("{}-rank", fixture.name));
+
+            RankIndex::build(fixture.link_index.as_ref().unwrap(), &path).unwrap();
+            fixture.rank_index = Some(RankIndex::open(&path, &fixture.title_index).unwrap());
+
+            fixture
+        }
+
+        // Serve body queries from the loaded articles instead of streaming the dump.
+        fn with_loaded_articles(mut self) -> Self {
+            self.loaded = true;
+            self
+        }
+
+        // Run `target` and return the titles of the resulting articles, or of the first element
+        // of resulting tuples.
+        fn run(&self, target: &str) -> Vec<String> {
+            self.try_run(target).unwrap()
+        }
+
+        fn try_run(&self, target: &str) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+            let irb = IRBuilder::from(target)?;
+
+            let head_inst = irb.build();
+            let insts = IRBuilder::ir_flatten(&head_inst);
+
+            let vm = vm_from!(insts);
+
+            let rt_ref = RuntimeRef {
+                category_index: &self.category_index,
+                category_tree: self.category_tree.as_ref(),
+                title_index: &self.title_index,
+                body_index: self.body_index.as_ref(),
+                link_index: self.link_index.as_ref(),
+                rank_index: self.rank_index.as_ref(),
+                articles: self.loaded.then(|| load_dump(&self.dump_path).unwrap()),
+            };
+
+            let result = vm.run(&rt_ref)?;
+
+            Ok(result
+                .data
+                .unwrap_array()
+                .iter()
+                .map(|x| match x {
+                    // scored results
+                    RuntimeVariableAbstractData::Tuple(x) => &x[0],
+                    x => x,
+                })
+                .map(|x| match x.unwrap_primitive() {
+                    RuntimeVariableAbstractPrimitiveData::Article(e) => e.title.clone(),
+                    RuntimeVariableAbstractPrimitiveData::Category(e) => e.to_string(),
+                    // mapped results
+                    RuntimeVariableAbstractPrimitiveData::Integer(e) => e.to_string(),
+                    RuntimeVariableAbstractPrimitiveData::Boolean(e) => e.to_string(),
+                    _ => unreachable!(),
+                })
+                .collect())
+        }
+    }
+
     fn _uncover_integer(rt_var: &RuntimeVariable) -> i64 {
         match &rt_var.data {
             RuntimeVariableAbstractData::Primitive(e) => match e {
@@ -0,0 +1,126 @@
+use std::{
+    error::Error,
+    fs,
+    io::{self, Read, Write},
+    ops::Deref,
+};
+
+use memmap2::Mmap;
+
+// Little-endian helpers shared by the memory-mapped index formats.
+
+pub fn map_file(path: &str) -> Result<Mmap, Box<dyn Error>> {
+    let file = fs::File::open(path)?;
+
+    // the index files are written once and never modified while mapped
+    Ok(unsafe { Mmap::map(&file)? })
+}
+
+// Bytes of an index built in memory or mapped from its file, queried the same way.
+pub enum IndexBytes {
+    Memory(Vec<u8>),
+    Mapped(Mmap),
+}
+
+impl Deref for IndexBytes {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            IndexBytes::Memory(bytes) => bytes,
+            IndexBytes::Mapped(mmap) => mmap,
+        }
+    }
+}
+
+pub fn has_magic(path: &str, magic: &[u8]) -> bool {
+    let mut buf = vec![0; magic.len()];
+
+    fs::File::open(path)
+        .and_then(|mut file| file.read_exact(&mut buf))
+        .map(|_| buf == magic)
+        .unwrap_or(false)
+}
+
+pub fn read_u32(buf: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(buf[offset..offset + 4].try_into().unwrap())
+}
+
+pub fn read_u64(buf: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(buf[offset..offset + 8].try_into().unwrap())
+}
+
+pub fn read_u32_from(reader: &mut impl Read) -> io::Result<u32> {
+    let mut buf = [0; 4];
+    reader.read_exact(&mut buf)?;
+
+    Ok(u32::from_le_bytes(buf))
+}
+
+pub fn read_u64_from(reader: &mut impl Read) -> io::Result<u64> {
+    let mut buf = [0; 8];
+    reader.read_exact(&mut buf)?;
+
+    Ok(u64::from_le_bytes(buf))
+}
+
+pub fn write_u32(writer: &mut impl Write, value: u32) -> io::Result<()> {
+    writer.write_all(&value.to_le_bytes())
+}
+
+pub fn write_u64(writer: &mut impl Write, value: u64) -> io::Result<()> {
+    writer.write_all(&value.to_le_bytes())
+}
+
+// LEB128 style variable length integer, 7 bits per byte.
+pub fn write_varint(buf: &mut Vec<u8>, mut value: u32) {
+    while value >= 0x80 {
+        buf.push((value as u8 & 0x7f) | 0x80);
+        value >>= 7;
+    }
+
+    buf.push(value as u8);
+}
+
+pub fn read_varint(buf: &[u8], offset: &mut usize) -> u32 {
+    let mut value = 0;
+    let mut shift = 0;
+
+    loop {
+        let byte = buf[*offset];
+        *offset += 1;
+
+        value |= ((byte & 0x7f) as u32) << shift;
+
+        if byte & 0x80 == 0 {
+            return value;
+        }
+
+        shift += 7;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{read_varint, write_varint};
+
+    #[test]
+    fn varint_test() {
+        let values = [0, 1, 127, 128, 300, 16384, u32::MAX];
+        let mut buf = Vec::new();
+
+        for value in values {
+            write_varint(&mut buf, value);
+        }
+
+        assert_eq!(buf.len(), 1 + 1 + 1 + 2 + 2 + 3 + 5);
+
+        let mut offset = 0;
+
+        for value in values {
+            assert_eq!(read_varint(&buf, &mut offset), value);
+        }
+
+        assert_eq!(offset, buf.len());
+    }
+}
@@ -0,0 +1,300 @@
+use std::{error::Error, fs};
+
+use super::binary::{map_file, read_u32, read_u64, IndexBytes};
+
+// BK-tree over titles for edit distance queries.
+// Nodes only keep title ids, the titles are borrowed from the title table on demand.
+
+// Binary BK-tree layout (little-endian), the same in memory and on disk
+//
+//   magic     8 bytes "HKRBKTRE"
+//   version   u32
+//   reserved  u32
+//   titles    u64, number of titles of the title index it was built from
+//   nodes     u64
+//   nodes     nodes * 12 bytes, the root first
+//             (title id u32, children offset u32, children count u32)
+//   children  (distance u32, child node u32) pairs, children offsets count pairs
+pub const BKTREE_MAGIC: &[u8] = b"HKRBKTRE";
+const BKTREE_VERSION: u32 = 1;
+const HEADER_SIZE: usize = 32;
+const NODE_SIZE: usize = 12;
+const CHILD_SIZE: usize = 8;
+
+pub struct BkTree {
+    bytes: IndexBytes,
+    titles: usize,
+    nodes: usize,
+}
+
+struct BkNode {
+    id: u32,
+    // (distance to this node, child node index)
+    children: Vec<(u32, u32)>,
+}
+
+impl BkTree {
+    pub fn build<'a>(len: usize, title: impl Fn(usize) -> &'a str) -> Self {
+        let mut nodes: Vec<BkNode> = Vec::with_capacity(len);
+
+        for id in 0..len {
+            if nodes.is_empty() {
+                nodes.push(BkNode {
+                    id: 0,
+                    children: Vec::new(),
+                });
+                continue;
+            }
+
+            let target: Vec<char> = title(id).chars().collect();
+            let mut node = 0;
+
+            loop {
+                let distance = levenshtein(&target, title(nodes[node].id as usize)) as u32;
+
+                // duplicated titles cannot exist in the title index
+                if distance == 0 {
+                    break;
+                }
+
+                match nodes[node].children.iter().find(|x| x.0 == distance) {
+                    Some(child) => node = child.1 as usize,
+                    None => {
+                        let child = nodes.len() as u32;
+
+                        nodes[node].children.push((distance, child));
+                        nodes.push(BkNode {
+                            id: id as u32,
+                            children: Vec::new(),
+                        });
+                        break;
+                    }
+                }
+            }
+        }
+
+        let mut bytes = Vec::new();
+
+        bytes.extend_from_slice(BKTREE_MAGIC);
+        bytes.extend_from_slice(&BKTREE_VERSION.to_le_bytes());
+        bytes.extend_from_slice(&0u32.to_le_bytes());
+        bytes.extend_from_slice(&(len as u64).to_le_bytes());
+        bytes.extend_from_slice(&(nodes.len() as u64).to_le_bytes());
+
+        let mut offset = 0;
+
+        for node in nodes.iter() {
+            bytes.extend_from_slice(&node.id.to_le_bytes());
+            bytes.extend_from_slice(&(offset as u32).to_le_bytes());
+            bytes.extend_from_slice(&(node.children.len() as u32).to_le_bytes());
+
+            offset += node.children.len();
+        }
+
+        for (distance, child) in nodes.iter().flat_map(|x| x.children.iter()) {
+            bytes.extend_from_slice(&distance.to_le_bytes());
+            bytes.extend_from_slice(&child.to_le_bytes());
+        }
+
+        BkTree {
+            bytes: IndexBytes::Memory(bytes),
+            titles: len,
+            nodes: nodes.len(),
+        }
+    }
+
+    pub fn write(&self, path: &str) -> Result<(), Box<dyn Error>> {
+        fs::write(path, &*self.bytes)?;
+
+        Ok(())
+    }
+
+    // Map a tree written by `write`, it must have been built from `titles` titles.
+    pub fn open(path: &str, titles: usize) -> Result<Self, Box<dyn Error>> {
+        let bytes = map_file(path)?;
+
+        if bytes.len() < HEADER_SIZE || &bytes[..8] != BKTREE_MAGIC {
+            return Err(format!("'{}' is not a BK-tree", path).into());
+        }
+
+        let version = read_u32(&bytes, 8);
+        if version != BKTREE_VERSION {
+            return Err(format!(
+                "Unsupported BK-tree version {} (expected {}), build it again",
+                version, BKTREE_VERSION
+            )
+            .into());
+        }
+
+        let tree = BkTree {
+            titles: read_u64(&bytes, 16) as usize,
+            nodes: read_u64(&bytes, 24) as usize,
+            bytes: IndexBytes::Mapped(bytes),
+        };
+
+        if tree.titles != titles {
+            return Err(format!(
+                "BK-tree '{}' has {} titles, the title index has {}",
+                path, tree.titles, titles
+            )
+            .into());
+        }
+
+        // every node but the root is a child once
+        let children = tree.nodes.saturating_sub(1);
+
+        if tree.bytes.len() < tree.children_table() + children * CHILD_SIZE {
+            return Err(format!("BK-tree '{}' is truncated", path).into());
+        }
+
+        for i in 0..tree.nodes {
+            let (id, offset, count) = tree.node(i);
+
+            if id >= titles
+                || offset + count > children
+                || (offset..offset + count).any(|k| tree.child(k).1 >= tree.nodes)
+            {
+                return Err(format!("BK-tree '{}' has a broken node at {}", path, i).into());
+            }
+        }
+
+        Ok(tree)
+    }
+
+    // Number of titles the tree was built from.
+    pub fn titles(&self) -> usize {
+        self.titles
+    }
+
+    fn children_table(&self) -> usize {
+        HEADER_SIZE + self.nodes * NODE_SIZE
+    }
+
+    // (title id, children offset, children count)
+    fn node(&self, i: usize) -> (usize, usize, usize) {
+        let entry = HEADER_SIZE + i * NODE_SIZE;
+
+        (
+            read_u32(&self.bytes, entry) as usize,
+            read_u32(&self.bytes, entry + 4) as usize,
+            read_u32(&self.bytes, entry + 8) as usize,
+        )
+    }
+
+    // (distance, child node)
+    fn child(&self, k: usize) -> (usize, usize) {
+        let entry = self.children_table() + k * CHILD_SIZE;
+
+        (
+            read_u32(&self.bytes, entry) as usize,
+            read_u32(&self.bytes, entry + 4) as usize,
+        )
+    }
+
+    // `(title id, distance)` of every title within `max_distance` of `what`.
+    pub fn find<'a>(
+        &self,
+        what: &str,
+        max_distance: usize,
+        title: impl Fn(usize) -> &'a str,
+    ) -> Vec<(usize, usize)> {
+        let mut result = Vec::new();
+
+        if self.nodes == 0 {
+            return result;
+        }
+
+        let target: Vec<char> = what.chars().collect();
+        let mut stack = vec![0];
+
+        while let Some(node) = stack.pop() {
+            let (id, offset, count) = self.node(node);
+            let distance = levenshtein(&target, title(id));
+
+            if distance <= max_distance {
+                result.push((id, distance));
+            }
+
+            let low = distance.saturating_sub(max_distance);
+            let high = distance + max_distance;
+
+            stack.extend(
+                (offset..offset + count)
+                    .map(|k| self.child(k))
+                    .filter(|x| low <= x.0 && x.0 <= high)
+                    .map(|x| x.1),
+            );
+        }
+
+        result
+    }
+}
+
+pub fn levenshtein(a: &[char], b: &str) -> usize {
+    let mut prev: Vec<usize> = (0..=a.len()).collect();
+    let mut cur: Vec<usize> = vec![0; a.len() + 1];
+
+    for (j, cb) in b.chars().enumerate() {
+        cur[0] = j + 1;
+
+        for (i, ca) in a.iter().enumerate() {
+            let substitute = prev[i] + if *ca == cb { 0 } else { 1 };
+            cur[i + 1] = substitute.min(prev[i + 1] + 1).min(cur[i] + 1);
+        }
+
+        std::mem::swap(&mut prev, &mut cur);
+    }
+
+    prev[a.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::loader::fixture::temp_path;
+
+    use super::{levenshtein, BkTree};
+
+    fn distance(a: &str, b: &str) -> usize {
+        levenshtein(&a.chars().collect::<Vec<_>>(), b)
+    }
+
+    #[test]
+    fn levenshtein_test() {
+        assert_eq!(distance("", ""), 0);
+        assert_eq!(distance("kitten", "sitting"), 3);
+        assert_eq!(distance("동방프로젝트", "동방프로젝트"), 0);
+        assert_eq!(distance("동방프로젝트", "동방 프로젝트"), 1);
+        assert_eq!(distance("하쿠레이", ""), 4);
+    }
+
+    #[test]
+    fn bktree_find_test() {
+        let titles = [
+            "동방프로젝트",
+            "동방 프로젝트",
+            "동방",
+            "서든어택",
+            "하쿠레이 신사",
+        ];
+        let tree = BkTree::build(titles.len(), |i| titles[i]);
+
+        let mut result = tree.find("동방프로잭트", 2, |i| titles[i]);
+        result.sort();
+
+        assert_eq!(result, vec![(0, 1), (1, 2)]);
+        assert_eq!(tree.find("서든 어택", 1, |i| titles[i]), vec![(3, 1)]);
+        assert!(tree.find("원신", 1, |i| titles[i]).is_empty());
+
+        // the mapped tree answers the same
+        let path = temp_path("bktree.bin");
+        tree.write(&path).unwrap();
+
+        let mapped = BkTree::open(&path, titles.len()).unwrap();
+        let mut result = mapped.find("동방프로잭트", 2, |i| titles[i]);
+        result.sort();
+
+        assert_eq!(mapped.titles(), 5);
+        assert_eq!(result, vec![(0, 1), (1, 2)]);
+        assert!(BkTree::open(&path, titles.len() - 1).is_err());
+    }
+}
@@ -0,0 +1,861 @@
+use std::{
+    collections::HashMap,
+    error::Error,
+    fs,
+    io::{self, BufReader, BufWriter, Read, Write},
+    mem, str,
+};
+
+use memmap2::Mmap;
+
+use crate::{
+    loader::load::{read_entry, DumpReader},
+    model::{article::Article, namumark::Document},
+};
+
+use super::binary::{
+    map_file, read_u32, read_u32_from, read_u64, read_u64_from, read_varint, write_u32, write_u64,
+    write_varint,
+};
+
+// Positional inverted index over article bodies.
+//
+// Bodies are rendered to plain text with `model::namumark`, which is then split into runs of
+// letters and digits. Every run is cut into character bigrams, a run of a single character is kept as is.
+// Hangul has no reliable word boundary (`프로젝트는` should still match `프로젝트`), so bigrams
+// over unicode scalars are used for every script. Characters are lowercased.
+//
+// Titles are indexed as a separate field without rendering, for ranking.
+// Redirect documents are not indexed.
+
+// Binary body index layout (little-endian)
+//
+//   magic     8 bytes "HKRBODY\0"
+//   version   u32
+//   reserved  u32
+//   docs      u64
+//   terms     u64
+//   postings  u64, total length of the postings
+//   tokens    u64 * 2, total token count of the body and title fields
+//   doc table docs * 24 bytes, in dump order
+//             (article start u64, article end u64, body token count u32, title token count u32)
+//   terms     terms * 64 bytes, sorted by term
+//             (term offset u64, term length u32, reserved u32,
+//              then for the body and the title field:
+//              doc frequency u32, reserved u32, postings offset u64, postings length u64)
+//   postings  per doc: varint doc id delta, varint tf, tf * varint position delta
+//   terms     utf-8 term pool, term offsets are relative to here
+pub const BODY_INDEX_MAGIC: &[u8] = b"HKRBODY\0";
+const BODY_INDEX_VERSION: u32 = 4;
+const HEADER_SIZE: usize = 56;
+const DOC_SIZE: usize = 24;
+const TERM_SIZE: usize = 64;
+const FIELD_SIZE: usize = 24;
+
+// Postings of a segment kept in memory while building, in bytes. Terms are counted with
+// `TERM_OVERHEAD` bytes for the map entry on top of their postings.
+const SEGMENT_MEMORY: usize = 512 << 20;
+const TERM_OVERHEAD: usize = 128;
+
+// BM25 parameters, the title field score is multiplied by `TITLE_BOOST`
+const BM25_K1: f64 = 1.2;
+const BM25_B: f64 = 0.75;
+const TITLE_BOOST: f64 = 3.0;
+
+#[derive(Clone, Copy)]
+pub enum BodyField {
+    Body = 0,
+    Title = 1,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Posting {
+    pub doc: u32,
+    pub positions: Vec<u32>,
+}
+
+pub struct BodyIndex {
+    mmap: Mmap,
+    docs: usize,
+    terms: usize,
+    term_table: usize,
+    postings: usize,
+    pool: usize,
+    tokens: [u64; 2],
+    file: fs::File,
+}
+
+impl BodyIndex {
+    // Scan the dump and write the body index, returns the number of indexed articles.
+    pub fn build(dump_path: &str, index_path: &str) -> Result<usize, Box<dyn Error>> {
+        Self::build_segmented(dump_path, index_path, SEGMENT_MEMORY)
+    }
+
+    // Postings are written to a sorted segment next to the index whenever they take about
+    // `segment_memory` bytes, the segments are merged into the index at the end.
+    fn build_segmented(
+        dump_path: &str,
+        index_path: &str,
+        segment_memory: usize,
+    ) -> Result<usize, Box<dyn Error>> {
+        let mut docs: Vec<(usize, usize, [u32; 2])> = Vec::new();
+        let mut terms: HashMap<String, [PostingWriter; 2]> = HashMap::new();
+        let mut memory = 0;
+        let mut segments: Vec<String> = Vec::new();
+
+        for entry in DumpReader::open(dump_path)? {
+            let entry = entry?;
+
+            if entry.article.is_redirect() {
+                continue;
+            }
+
+            let doc = docs.len() as u32;
+            let mut lens = [0; 2];
+
+            for (field, text) in [
+                (BodyField::Body, &entry.article.text),
+                (BodyField::Title, &entry.article.title),
+            ] {
+                let tokens = match field {
+                    BodyField::Body => tokenize(text),
+                    BodyField::Title => tokenize_plain(text),
+                };
+                let mut positions: HashMap<&str, Vec<u32>> = HashMap::new();
+
+                for (position, token) in tokens.iter().enumerate() {
+                    positions.entry(token).or_default().push(position as u32);
+                }
+
+                for (term, positions) in positions {
+                    if !terms.contains_key(term) {
+                        terms.insert(term.to_owned(), Default::default());
+                        memory += term.len() + TERM_OVERHEAD;
+                    }
+
+                    let posting = &mut terms.get_mut(term).unwrap()[field as usize];
+                    let len = posting.bytes.len();
+
+                    posting.push(doc, &positions);
+                    memory += posting.bytes.len() - len;
+                }
+
+                lens[field as usize] = tokens.len() as u32;
+            }
+
+            docs.push((entry.start, entry.end, lens));
+
+            if memory >= segment_memory {
+                let path = format!("{}.segment{}", index_path, segments.len());
+
+                Self::write_segment(&path, mem::take(&mut terms))?;
+                segments.push(path);
+                memory = 0;
+            }
+        }
+
+        if !terms.is_empty() || segments.is_empty() {
+            let path = format!("{}.segment{}", index_path, segments.len());
+
+            Self::write_segment(&path, terms)?;
+            segments.push(path);
+        }
+
+        let result = Self::write(index_path, &docs, &segments);
+
+        for segment in segments.iter() {
+            fs::remove_file(segment)?;
+        }
+
+        result?;
+
+        Ok(docs.len())
+    }
+
+    // Segment layout, terms in sorted order:
+    //   term length u32, term, then for the body and the title field:
+    //   doc frequency u32, last doc u32, postings length u64, postings
+    fn write_segment(
+        path: &str,
+        terms: HashMap<String, [PostingWriter; 2]>,
+    ) -> Result<(), Box<dyn Error>> {
+        let mut terms: Vec<(String, [PostingWriter; 2])> = terms.into_iter().collect();
+        terms.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let mut writer = BufWriter::new(fs::File::create(path)?);
+
+        for (term, postings) in terms.iter() {
+            write_u32(&mut writer, term.len() as u32)?;
+            writer.write_all(term.as_bytes())?;
+
+            for posting in postings {
+                write_u32(&mut writer, posting.doc_freq)?;
+                write_u32(&mut writer, posting.last_doc)?;
+                write_u64(&mut writer, posting.bytes.len() as u64)?;
+                writer.write_all(&posting.bytes)?;
+            }
+        }
+
+        writer.flush()?;
+
+        Ok(())
+    }
+
+    // Merge the segments into the index. The term table, the postings and the term pool are
+    // written to temporary files first, the header needs their sizes.
+    fn write(
+        path: &str,
+        docs: &[(usize, usize, [u32; 2])],
+        segments: &[String],
+    ) -> Result<(), Box<dyn Error>> {
+        let mut readers = segments
+            .iter()
+            .map(|x| SegmentReader::open(x))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let parts = ["terms", "postings", "pool"].map(|x| format!("{}.{}", path, x));
+        let mut table = BufWriter::new(fs::File::create(&parts[0])?);
+        let mut postings = BufWriter::new(fs::File::create(&parts[1])?);
+        let mut pool = BufWriter::new(fs::File::create(&parts[2])?);
+
+        let mut terms = 0;
+        let mut term_offset = 0;
+        let mut postings_offset = 0;
+
+        while let Some(term) = readers
+            .iter()
+            .filter_map(|x| x.head.as_ref().map(|x| &x.0))
+            .min()
+            .cloned()
+        {
+            let matched: Vec<&SegmentReader> = readers
+                .iter()
+                .filter(|x| x.head.as_ref().is_some_and(|x| x.0 == term))
+                .collect();
+
+            write_u64(&mut table, term_offset as u64)?;
+            write_u32(&mut table, term.len() as u32)?;
+            write_u32(&mut table, 0)?;
+
+            for field in [BodyField::Body, BodyField::Title] {
+                let mut doc_freq = 0;
+                let mut last_doc = 0;
+                let mut len = 0;
+
+                // segments hold consecutive docs, in order
+                for reader in matched.iter() {
+                    let posting = &reader.head.as_ref().unwrap().1[field as usize];
+
+                    if posting.doc_freq == 0 {
+                        continue;
+                    }
+
+                    len += posting.write_after(&mut postings, last_doc)?;
+                    doc_freq += posting.doc_freq;
+                    last_doc = posting.last_doc;
+                }
+
+                write_u32(&mut table, doc_freq)?;
+                write_u32(&mut table, 0)?;
+                write_u64(&mut table, postings_offset as u64)?;
+                write_u64(&mut table, len as u64)?;
+
+                postings_offset += len;
+            }
+
+            pool.write_all(term.as_bytes())?;
+            term_offset += term.len();
+            terms += 1;
+
+            for reader in readers.iter_mut() {
+                if reader.head.as_ref().is_some_and(|x| x.0 == term) {
+                    reader.advance()?;
+                }
+            }
+        }
+
+        table.flush()?;
+        postings.flush()?;
+        pool.flush()?;
+
+        let mut writer = BufWriter::new(fs::File::create(path)?);
+
+        writer.write_all(BODY_INDEX_MAGIC)?;
+        write_u32(&mut writer, BODY_INDEX_VERSION)?;
+        write_u32(&mut writer, 0)?;
+        write_u64(&mut writer, docs.len() as u64)?;
+        write_u64(&mut writer, terms as u64)?;
+        write_u64(&mut writer, postings_offset as u64)?;
+
+        for field in [BodyField::Body, BodyField::Title] {
+            write_u64(
+                &mut writer,
+                docs.iter().map(|x| x.2[field as usize] as u64).sum(),
+            )?;
+        }
+
+        for (start, end, lens) in docs {
+            write_u64(&mut writer, *start as u64)?;
+            write_u64(&mut writer, *end as u64)?;
+            write_u32(&mut writer, lens[0])?;
+            write_u32(&mut writer, lens[1])?;
+        }
+
+        for part in parts.iter() {
+            io::copy(&mut fs::File::open(part)?, &mut writer)?;
+            fs::remove_file(part)?;
+        }
+
+        writer.flush()?;
+
+        Ok(())
+    }
+
+    // Only the header is checked, terms and postings are checked when they are looked up.
+    pub fn open(dump_path: &str, index_path: &str) -> Result<Self, Box<dyn Error>> {
+        let mmap = map_file(index_path)?;
+
+        if mmap.len() < HEADER_SIZE || &mmap[..8] != BODY_INDEX_MAGIC {
+            return Err(format!("'{}' is not a body index", index_path).into());
+        }
+
+        let version = read_u32(&mmap, 8);
+        if version != BODY_INDEX_VERSION {
+            return Err(format!(
+                "Unsupported body index version {} (expected {}), build it again",
+                version, BODY_INDEX_VERSION
+            )
+            .into());
+        }
+
+        let docs = read_u64(&mmap, 16) as usize;
+        let terms = read_u64(&mmap, 24) as usize;
+        let term_table = HEADER_SIZE + docs * DOC_SIZE;
+        let postings = term_table + terms * TERM_SIZE;
+        let pool = postings + read_u64(&mmap, 32) as usize;
+
+        if mmap.len() < pool {
+            return Err(format!("Body index '{}' is truncated", index_path).into());
+        }
+
+        Ok(BodyIndex {
+            tokens: [read_u64(&mmap, 40), read_u64(&mmap, 48)],
+            mmap,
+            docs,
+            terms,
+            term_table,
+            postings,
+            pool,
+            file: fs::File::open(dump_path)?,
+        })
+    }
+
+    // Number of indexed articles.
+    pub fn len(&self) -> usize {
+        self.docs
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.docs == 0
+    }
+
+    pub fn doc_range(&self, doc: u32) -> (usize, usize) {
+        let entry = HEADER_SIZE + doc as usize * DOC_SIZE;
+
+        (
+            read_u64(&self.mmap, entry) as usize,
+            read_u64(&self.mmap, entry + 8) as usize,
+        )
+    }
+
+    // Number of tokens in the body or the title of `doc`.
+    pub fn doc_len(&self, doc: u32, field: BodyField) -> usize {
+        read_u32(
+            &self.mmap,
+            HEADER_SIZE + doc as usize * DOC_SIZE + 16 + field as usize * 4,
+        ) as usize
+    }
+
+    pub fn article(&self, doc: u32) -> Result<Article, Box<dyn Error>> {
+        let (start, end) = self.doc_range(doc);
+
+        read_entry(&self.file, start, end)
+    }
+
+    fn term_span(&self, i: usize) -> (usize, usize) {
+        let entry = self.term_table + i * TERM_SIZE;
+
+        (
+            self.pool + read_u64(&self.mmap, entry) as usize,
+            read_u32(&self.mmap, entry + 8) as usize,
+        )
+    }
+
+    fn field_entry(&self, i: usize, field: BodyField) -> usize {
+        self.term_table + i * TERM_SIZE + 16 + field as usize * FIELD_SIZE
+    }
+
+    fn postings_span(&self, i: usize, field: BodyField) -> (usize, usize) {
+        let entry = self.field_entry(i, field);
+
+        (
+            self.postings + read_u64(&self.mmap, entry + 8) as usize,
+            read_u64(&self.mmap, entry + 16) as usize,
+        )
+    }
+
+    fn term(&self, i: usize) -> Result<&str, Box<dyn Error>> {
+        let (offset, len) = self.term_span(i);
+
+        self.mmap
+            .get(offset..offset + len)
+            .and_then(|x| str::from_utf8(x).ok())
+            .ok_or_else(|| format!("Body index has a broken term at {}", i).into())
+    }
+
+    fn find_term(&self, term: &str) -> Result<Option<usize>, Box<dyn Error>> {
+        let (mut lo, mut hi) = (0, self.terms);
+
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+
+            if self.term(mid)? < term {
+                lo = mid + 1;
+            } else {
+                hi = mid;
+            }
+        }
+
+        if lo < self.terms && self.term(lo)? == term {
+            Ok(Some(lo))
+        } else {
+            Ok(None)
+        }
+    }
+
+    // Number of articles containing `term` in `field`.
+    pub fn doc_freq(&self, term: &str, field: BodyField) -> Result<usize, Box<dyn Error>> {
+        Ok(self.find_term(term)?.map_or(0, |i| {
+            read_u32(&self.mmap, self.field_entry(i, field)) as usize
+        }))
+    }
+
+    // Postings of `term` in `field` sorted by doc id, `None` if the term is not indexed.
+    pub fn postings(
+        &self,
+        term: &str,
+        field: BodyField,
+    ) -> Result<Option<Vec<Posting>>, Box<dyn Error>> {
+        let i = match self.find_term(term)? {
+            Some(i) => i,
+            None => return Ok(None),
+        };
+        let (offset, len) = self.postings_span(i, field);
+
+        if offset + len > self.pool {
+            return Err(format!("Body index has broken postings at {}", i).into());
+        }
+
+        let buf = &self.mmap[offset..offset + len];
+
+        let mut result = Vec::new();
+        let mut cursor = 0;
+        let mut doc = 0;
+
+        while cursor < buf.len() {
+            doc += read_varint(buf, &mut cursor);
+
+            let tf = read_varint(buf, &mut cursor);
+            let mut positions = Vec::with_capacity(tf as usize);
+            let mut position = 0;
+
+            for _ in 0..tf {
+                position += read_varint(buf, &mut cursor);
+                positions.push(position);
+            }
+
+            result.push(Posting { doc, positions });
+        }
+
+        Ok(Some(result))
+    }
+
+    // Sorted doc ids of articles whose tokens contain the tokens of `what` at consecutive
+    // positions. Candidates must still be verified against the text,
+    // `None` means the query has no token and every article is a candidate.
+    pub fn candidates(&self, what: &str) -> Result<Option<Vec<u32>>, Box<dyn Error>> {
+        let tokens = query_tokens(what);
+
+        if tokens.is_empty() {
+            return Ok(None);
+        }
+
+        let mut postings: HashMap<&str, Vec<Posting>> = HashMap::new();
+
+        for (term, _) in &tokens {
+            if postings.contains_key(&term[..]) {
+                continue;
+            }
+
+            match self.postings(term, BodyField::Body)? {
+                Some(p) => postings.insert(term, p),
+                None => return Ok(Some(Vec::new())),
+            };
+        }
+
+        // walk the rarest term and look the others up by doc id
+        let (anchor, _) = tokens
+            .iter()
+            .min_by_key(|(term, _)| postings[&term[..]].len())
+            .unwrap();
+
+        let mut result = Vec::new();
+
+        for posting in &postings[&anchor[..]] {
+            let positions: Option<Vec<(&[u32], u32)>> = tokens
+                .iter()
+                .map(|(term, offset)| {
+                    let p = &postings[&term[..]];
+
+                    p.binary_search_by_key(&posting.doc, |x| x.doc)
+                        .ok()
+                        .map(|i| (&p[i].positions[..], *offset))
+                })
+                .collect();
+
+            let positions = match positions {
+                Some(positions) => positions,
+                None => continue,
+            };
+
+            let (first, first_offset) = positions[0];
+
+            let found = first.iter().any(|p| {
+                positions.iter().all(|(positions, offset)| {
+                    positions
+                        .binary_search(&(p - first_offset + offset))
+                        .is_ok()
+                })
+            });
+
+            if found {
+                result.push(posting.doc);
+            }
+        }
+
+        Ok(Some(result))
+    }
+
+    // Articles containing any token of `what` ranked by BM25 over the body plus the boosted
+    // BM25 over the title, best first. Ties are broken by dump order.
+    pub fn search(&self, what: &str) -> Result<Vec<(u32, f64)>, Box<dyn Error>> {
+        let mut terms = tokenize_plain(what);
+        terms.sort();
+        terms.dedup();
+
+        let mut scores: HashMap<u32, f64> = HashMap::new();
+
+        for term in &terms {
+            for (field, boost) in [(BodyField::Body, 1.0), (BodyField::Title, TITLE_BOOST)] {
+                let postings = match self.postings(term, field)? {
+                    Some(p) if !p.is_empty() => p,
+                    _ => continue,
+                };
+
+                let df = postings.len() as f64;
+                let idf = (1.0 + (self.docs as f64 - df + 0.5) / (df + 0.5)).ln();
+                let avg_len = self.tokens[field as usize] as f64 / self.docs as f64;
+
+                for posting in postings {
+                    let tf = posting.positions.len() as f64;
+                    let len = self.doc_len(posting.doc, field) as f64;
+                    let norm = BM25_K1 * (1.0 - BM25_B + BM25_B * len / avg_len);
+
+                    *scores.entry(posting.doc).or_default() +=
+                        boost * idf * tf * (BM25_K1 + 1.0) / (tf + norm);
+                }
+            }
+        }
+
+        let mut result: Vec<(u32, f64)> = scores.into_iter().collect();
+        result.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+
+        Ok(result)
+    }
+}
+
+#[derive(Default)]
+struct PostingWriter {
+    bytes: Vec<u8>,
+    last_doc: u32,
+    doc_freq: u32,
+}
+
+impl PostingWriter {
+    // docs are pushed in increasing order
+    fn push(&mut self, doc: u32, positions: &[u32]) {
+        write_varint(&mut self.bytes, doc - self.last_doc);
+        write_varint(&mut self.bytes, positions.len() as u32);
+
+        let mut last = 0;
+
+        for position in positions {
+            write_varint(&mut self.bytes, position - last);
+            last = *position;
+        }
+
+        self.last_doc = doc;
+        self.doc_freq += 1;
+    }
+
+    // Write the postings after postings ending at `last_doc`, the first doc id delta is relative
+    // to it instead of 0. Returns the number of written bytes.
+    fn write_after(&self, writer: &mut impl Write, last_doc: u32) -> io::Result<usize> {
+        let mut cursor = 0;
+        let first = read_varint(&self.bytes, &mut cursor);
+        let mut head = Vec::new();
+
+        write_varint(&mut head, first - last_doc);
+        writer.write_all(&head)?;
+        writer.write_all(&self.bytes[cursor..])?;
+
+        Ok(head.len() + self.bytes.len() - cursor)
+    }
+}
+
+// Terms of a segment in sorted order, `head` is the current one.
+struct SegmentReader {
+    reader: BufReader<fs::File>,
+    head: Option<(String, [PostingWriter; 2])>,
+}
+
+impl SegmentReader {
+    fn open(path: &str) -> Result<Self, Box<dyn Error>> {
+        let mut segment = SegmentReader {
+            reader: BufReader::new(fs::File::open(path)?),
+            head: None,
+        };
+
+        segment.advance()?;
+
+        Ok(segment)
+    }
+
+    fn advance(&mut self) -> Result<(), Box<dyn Error>> {
+        let len = match read_u32_from(&mut self.reader) {
+            Ok(len) => len as usize,
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => {
+                self.head = None;
+                return Ok(());
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut term = vec![0; len];
+        self.reader.read_exact(&mut term)?;
+
+        let mut postings: [PostingWriter; 2] = Default::default();
+
+        for posting in postings.iter_mut() {
+            posting.doc_freq = read_u32_from(&mut self.reader)?;
+            posting.last_doc = read_u32_from(&mut self.reader)?;
+            posting.bytes = vec![0; read_u64_from(&mut self.reader)? as usize];
+            self.reader.read_exact(&mut posting.bytes)?;
+        }
+
+        self.head = Some((String::from_utf8(term)?, postings));
+
+        Ok(())
+    }
+}
+
+// The text of an article as rendered, see `Document::to_plain_text`.
+pub fn strip_namumark(text: &str) -> String {
+    Document::parse(text).to_plain_text()
+}
+
+fn words(text: &str) -> Vec<Vec<char>> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|x| !x.is_empty())
+        .map(|x| {
+            x.chars()
+                .map(|c| c.to_lowercase().next().unwrap_or(c))
+                .collect()
+        })
+        .collect()
+}
+
+fn word_tokens(word: &[char]) -> Vec<String> {
+    if word.len() == 1 {
+        vec![word[0].to_string()]
+    } else {
+        word.windows(2).map(|w| w.iter().collect()).collect()
+    }
+}
+
+// Tokens of an article body, the position of a token is its index.
+pub fn tokenize(text: &str) -> Vec<String> {
+    tokenize_plain(&strip_namumark(text))
+}
+
+// Tokens of a text which is not namumark, such as a title or a search query.
+pub fn tokenize_plain(text: &str) -> Vec<String> {
+    words(text).iter().flat_map(|x| word_tokens(x)).collect()
+}
+
+// Tokens of a query with their positions relative to the first one.
+// The first and last words may be part of a longer word in the text, their bigrams are
+// still found there but a single character cannot be, so it is dropped.
+fn query_tokens(what: &str) -> Vec<(String, u32)> {
+    let text = strip_namumark(what);
+    // every rendered block ends with a line break
+    let text = text.trim_end_matches('\n');
+    let words = words(text);
+
+    let open_start = text.chars().next().is_some_and(|c| c.is_alphanumeric());
+    let open_end = text.chars().last().is_some_and(|c| c.is_alphanumeric());
+
+    let mut result = Vec::new();
+    let mut position = 0;
+
+    for (i, word) in words.iter().enumerate() {
+        let partial = (i == 0 && open_start) || (i == words.len() - 1 && open_end);
+
+        if word.len() == 1 && partial {
+            continue;
+        }
+
+        for token in word_tokens(word) {
+            result.push((token, position));
+            position += 1;
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::Path};
+
+    use crate::loader::fixture::{temp_path, write_dump};
+
+    use super::{query_tokens, strip_namumark, tokenize, BodyField, BodyIndex};
+
+    #[test]
+    fn body_tokenize_test() {
+        assert_eq!(tokenize("동방 A1"), vec!["동방", "a1"]);
+        assert_eq!(
+            tokenize("'''동방'''프로젝트는"),
+            vec!["동방", "방프", "프로", "로젝", "젝트", "트는"]
+        );
+        assert_eq!(tokenize("[[하쿠레이 신사|신사]]"), vec!["신사"]);
+        assert_eq!(tokenize("[목차]\n== 개 요 =="), vec!["개", "요"]);
+        assert_eq!(
+            tokenize("||<bgcolor=#fff> {{{#red 빨강}}} ||"),
+            vec!["빨강"]
+        );
+        assert!(!strip_namumark("{{{#!wiki style=\"color: red\"\n본문}}}").contains("color"));
+
+        let query = |x| {
+            query_tokens(x)
+                .into_iter()
+                .map(|(t, p)| format!("{}@{}", t, p))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(query("방프로"), vec!["방프@0", "프로@1"]);
+        assert_eq!(query("방 프로"), vec!["프로@0"]);
+        assert_eq!(query(" 방 프로"), vec!["방@0", "프로@1"]);
+        assert!(query("동").is_empty());
+    }
+
+    #[test]
+    fn body_index_test() {
+        let dump_path = write_dump(
+            "body.json",
+            &[
+                ("동방프로젝트", "'''동방 프로젝트'''는 탄막 슈팅 게임이다."),
+                ("레이무", "#redirect 하쿠레이 레이무"),
+                (
+                    "하쿠레이 레이무",
+                    "[[동방 프로젝트]]의 주인공. 탄막을 쏜다.",
+                ),
+                (
+                    "서든어택",
+                    "온라인 FPS 슈팅 게임. 프로젝트 동방과 관계없다.",
+                ),
+            ],
+        );
+        let index_path = temp_path("body-index.bin");
+
+        assert_eq!(BodyIndex::build(&dump_path, &index_path).unwrap(), 3);
+
+        let index = BodyIndex::open(&dump_path, &index_path).unwrap();
+
+        assert_eq!(index.len(), 3);
+        assert_eq!(index.doc_freq("탄막", BodyField::Body).unwrap(), 2);
+        assert_eq!(index.doc_freq("레이", BodyField::Body).unwrap(), 0);
+        assert_eq!(index.doc_freq("레이", BodyField::Title).unwrap(), 1);
+        assert_eq!(index.doc_len(1, BodyField::Title), 5);
+        assert_eq!(index.article(1).unwrap().title, "하쿠레이 레이무");
+
+        let postings = index.postings("슈팅", BodyField::Body).unwrap().unwrap();
+        assert_eq!(
+            postings.iter().map(|x| x.doc).collect::<Vec<_>>(),
+            vec![0, 2]
+        );
+        assert_eq!(postings[0].positions, vec![6]);
+
+        let candidates = |what| index.candidates(what).unwrap();
+
+        assert_eq!(candidates("동방 프로젝트"), Some(vec![0, 1]));
+        assert_eq!(candidates("방 프로젝"), Some(vec![0, 1, 2]));
+        assert_eq!(candidates("슈팅 게임"), Some(vec![0, 2]));
+        assert_eq!(candidates("fps"), Some(vec![2]));
+        assert_eq!(candidates("게임 슈팅"), Some(vec![]));
+        assert_eq!(candidates("원신"), Some(vec![]));
+        assert_eq!(candidates("탄"), None);
+
+        let ranked = |what| {
+            index
+                .search(what)
+                .unwrap()
+                .into_iter()
+                .map(|x| x.0)
+                .collect::<Vec<_>>()
+        };
+
+        // the title match ranks first
+        assert_eq!(ranked("동방프로젝트")[0], 0);
+        assert_eq!(ranked("동방프로젝트").len(), 3);
+        assert_eq!(ranked("서든"), vec![2]);
+        assert_eq!(ranked("탄막 레이무"), vec![1, 0]);
+        assert!(ranked("원신").is_empty());
+
+        // postings flushed to a segment per article merge to the same index
+        let segmented_path = temp_path("body-index-segmented.bin");
+
+        assert_eq!(
+            BodyIndex::build_segmented(&dump_path, &segmented_path, 1).unwrap(),
+            3
+        );
+        assert_eq!(
+            fs::read(&segmented_path).unwrap(),
+            fs::read(&index_path).unwrap()
+        );
+        assert!(!Path::new(&format!("{}.segment0", segmented_path)).exists());
+
+        // a broken term pool is found by the lookups, not by `open`
+        let mut bytes = fs::read(&segmented_path).unwrap();
+        let pool = index.pool;
+
+        bytes[pool..].fill(0xff);
+        fs::write(&segmented_path, bytes).unwrap();
+
+        let broken = BodyIndex::open(&dump_path, &segmented_path).unwrap();
+
+        assert!(broken.doc_freq("탄막", BodyField::Body).is_err());
+        assert!(broken.search("탄막").is_err());
+    }
+}
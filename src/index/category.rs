@@ -1,33 +1,283 @@
-use std::{collections::HashMap, error::Error, fs};
+use std::{
+    collections::{BTreeMap, HashMap},
+    error::Error,
+    fs,
+    io::{BufWriter, Write},
+    path::Path,
+};
 
-use crate::model::article_category::ArticleCategory;
+use serde::{Deserialize, Serialize};
 
+use crate::{loader::load::DumpReader, model::article_category::ArticleCategory};
+
+// `title -> [category]` of every article and the inverted `category -> [title]` map.
+//
+// The inverted map is written next to the index (`article-with-categories.members.json`), its
+// categories and their titles are sorted. Members are counted like in `CategoryTree`, see
+// `Article::is_category_member`. Without the map only `category:*` queries fail.
 pub struct CategoryIndex {
     map: HashMap<String, Vec<String>>,
+    members: Option<Vec<CategoryMembers>>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CategoryMembers {
+    category: String,
+    titles: Vec<String>,
+}
+
+pub enum CategoryFindOption {
+    Exact,
+    StartsWith,
+    Contains,
 }
 
 impl CategoryIndex {
+    pub fn build(dump_path: &str, index_path: &str) -> Result<usize, Box<dyn Error>> {
+        let mut writer = BufWriter::new(fs::File::create(index_path)?);
+        let mut members: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        let mut count = 0;
+
+        writer.write_all(b"[")?;
+
+        for entry in DumpReader::open(dump_path)? {
+            let article = entry?.article;
+            let article_category = article.to_article_category();
+
+            if count != 0 {
+                writer.write_all(b",")?;
+            }
+
+            serde_json::to_writer(&mut writer, &article_category)?;
+            count += 1;
+
+            if !article.is_category_member() {
+                continue;
+            }
+
+            for category in article_category.categories {
+                members
+                    .entry(category.to_owned())
+                    .or_default()
+                    .push(article.title.to_owned());
+            }
+        }
+
+        writer.write_all(b"]")?;
+        writer.flush()?;
+
+        let members: Vec<CategoryMembers> = members
+            .into_iter()
+            .map(|(category, mut titles)| {
+                titles.sort_unstable();
+                CategoryMembers { category, titles }
+            })
+            .collect();
+
+        let mut writer = BufWriter::new(fs::File::create(Self::members_path(index_path))?);
+        serde_json::to_writer(&mut writer, &members)?;
+        writer.flush()?;
+
+        Ok(count)
+    }
+
+    // `article-with-categories.json` => `article-with-categories.members.json`
+    pub fn members_path(index_path: &str) -> String {
+        match index_path.strip_suffix(".json") {
+            Some(stem) => format!("{}.members.json", stem),
+            None => format!("{}.members", index_path),
+        }
+    }
+
+    // Load the index and the inverted map next to it. The map cannot be rebuilt from the index,
+    // which does not tell redirects apart.
     pub fn load(index_path: &str) -> Result<Self, Box<dyn Error>> {
         let mut raw = fs::read_to_string(index_path)?;
 
-        unsafe {
+        let map: HashMap<String, Vec<String>> = unsafe {
             let js: Vec<ArticleCategory> = simd_json::from_str(&mut raw)?;
 
-            Ok(CategoryIndex {
-                map: js
-                    .into_iter()
-                    .map(|x| {
-                        (
-                            x.title.to_owned(),
-                            x.categories.into_iter().map(|x| x.to_owned()).collect(),
-                        )
-                    })
-                    .collect(),
-            })
-        }
+            js.into_iter()
+                .map(|x| {
+                    (
+                        x.title.to_owned(),
+                        x.categories.into_iter().map(|x| x.to_owned()).collect(),
+                    )
+                })
+                .collect()
+        };
+
+        let members_path = Self::members_path(index_path);
+
+        let members = if Path::new(&members_path).exists() {
+            let mut raw = fs::read_to_string(&members_path)?;
+            Some(unsafe { simd_json::from_str(&mut raw)? })
+        } else {
+            None
+        };
+
+        Ok(CategoryIndex { map, members })
     }
 
     pub fn get(&self, key: &str) -> Option<&Vec<String>> {
         self.map.get(key)
     }
+
+    // Whether the inverted map was loaded, `members` and `find_by` find nothing without it.
+    pub fn has_members(&self) -> bool {
+        self.members.is_some()
+    }
+
+    fn all_members(&self) -> &[CategoryMembers] {
+        self.members.as_deref().unwrap_or(&[])
+    }
+
+    fn find(&self, category: &str) -> Option<&CategoryMembers> {
+        let members = self.all_members();
+        let i = members.partition_point(|x| x.category.as_str() < category);
+
+        members.get(i).filter(|x| x.category == category)
+    }
+
+    // Sorted titles of the articles in `category`.
+    pub fn members(&self, category: &str) -> &[String] {
+        self.find(category).map_or(&[], |x| &x.titles[..])
+    }
+
+    // Number of articles in `category`.
+    pub fn count(&self, category: &str) -> usize {
+        self.members(category).len()
+    }
+
+    // Sorted categories starting with `prefix`, paired with their number of articles.
+    pub fn categories_matching(&self, prefix: &str) -> Vec<(&str, usize)> {
+        let members = self.all_members();
+        let start = members.partition_point(|x| x.category.as_str() < prefix);
+
+        members[start..]
+            .iter()
+            .take_while(|x| x.category.starts_with(prefix))
+            .map(|x| (&x.category[..], x.titles.len()))
+            .collect()
+    }
+
+    // Sorted titles of the articles in any category matching `what`.
+    pub fn find_by(&self, what: &str, option: CategoryFindOption) -> Vec<&str> {
+        let matched: Vec<&CategoryMembers> = match option {
+            CategoryFindOption::Exact => self.find(what).into_iter().collect(),
+            CategoryFindOption::StartsWith => self
+                .categories_matching(what)
+                .into_iter()
+                .filter_map(|x| self.find(x.0))
+                .collect(),
+            CategoryFindOption::Contains => self
+                .all_members()
+                .iter()
+                .filter(|x| x.category.contains(what))
+                .collect(),
+        };
+
+        let mut result: Vec<&str> = matched
+            .into_iter()
+            .flat_map(|x| x.titles.iter().map(|x| &x[..]))
+            .collect();
+
+        if result.len() > 1 {
+            result.sort_unstable();
+            result.dedup();
+        }
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::loader::fixture::{temp_path, write_dump};
+
+    use super::{CategoryFindOption, CategoryIndex};
+
+    #[test]
+    fn category_index_test() {
+        let dump_path = write_dump(
+            "category.json",
+            &[
+                (
+                    "너의 이름은.",
+                    "[[분류:일본 애니메이션/2016년]][[분류:신카이 마코토]]",
+                ),
+                ("레이무", "#redirect 하쿠레이 레이무\n[[분류:동방]]"),
+                ("하쿠레이 레이무", "[[분류:동방 프로젝트/등장인물#blur]]"),
+                (
+                    "날씨의 아이",
+                    "[[분류:일본 애니메이션/2019년]][[분류:신카이 마코토]][[분류:신카이 마코토]]",
+                ),
+                ("일본 애니메이션", "[[분류:일본 애니메이션]]"),
+                ("분류:신카이 마코토", "[[분류:일본 애니메이션]]"),
+            ],
+        );
+        let index_path = temp_path("category-index.json");
+
+        assert_eq!(CategoryIndex::build(&dump_path, &index_path).unwrap(), 6);
+
+        let index = CategoryIndex::load(&index_path).unwrap();
+
+        assert_eq!(
+            index.get("하쿠레이 레이무").unwrap(),
+            &vec!["동방 프로젝트/등장인물"]
+        );
+        assert_eq!(
+            index.get("날씨의 아이").unwrap(),
+            &vec!["일본 애니메이션/2019년", "신카이 마코토"]
+        );
+        assert_eq!(
+            index.members("신카이 마코토"),
+            ["날씨의 아이", "너의 이름은."]
+        );
+        assert_eq!(index.count("신카이 마코토"), 2);
+        // category pages are not members, like in `CategoryTree`
+        assert_eq!(index.count("일본 애니메이션"), 1);
+        assert_eq!(index.count("동방"), 0);
+        assert_eq!(
+            index.categories_matching("일본 애니메이션"),
+            vec![
+                ("일본 애니메이션", 1),
+                ("일본 애니메이션/2016년", 1),
+                ("일본 애니메이션/2019년", 1)
+            ]
+        );
+
+        let find = |what, option| index.find_by(what, option);
+
+        assert_eq!(
+            find("일본 애니메이션/", CategoryFindOption::StartsWith),
+            vec!["날씨의 아이", "너의 이름은."]
+        );
+        assert_eq!(
+            find("일본 애니메이션", CategoryFindOption::Exact),
+            vec!["일본 애니메이션"]
+        );
+        assert_eq!(
+            find("등장인물", CategoryFindOption::Contains),
+            vec!["하쿠레이 레이무"]
+        );
+        assert!(find("동방", CategoryFindOption::Exact).is_empty());
+
+        // redirects are not members, the index alone cannot tell
+        assert!(index.members("동방").is_empty());
+        assert!(index.has_members());
+
+        // without the inverted map categories of articles are still found
+        fs::remove_file(CategoryIndex::members_path(&index_path)).unwrap();
+        let index = CategoryIndex::load(&index_path).unwrap();
+
+        assert!(!index.has_members());
+        assert_eq!(
+            index.get("하쿠레이 레이무").unwrap(),
+            &vec!["동방 프로젝트/등장인물"]
+        );
+        assert!(index.members("신카이 마코토").is_empty());
+    }
 }
@@ -0,0 +1,233 @@
+use std::{
+    collections::{HashMap, HashSet},
+    error::Error,
+    fs,
+    io::{BufWriter, Write},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::loader::load::DumpReader;
+
+// Category hierarchy of the wiki.
+//
+// Category pages (the `분류` namespace) are categorized like documents, the categories of a
+// category page are its parents. Documents are the members of their categories, redirects and
+// other namespaces are not, see `Article::is_category_member`. The hierarchy is not a tree in practice, categories can have many
+// parents and form cycles.
+
+#[derive(Default, Serialize, Deserialize)]
+struct CategoryNode {
+    name: String,
+    parents: Vec<String>,
+    articles: Vec<String>,
+}
+
+pub struct CategoryTree {
+    nodes: HashMap<String, CategoryNode>,
+    children: HashMap<String, Vec<String>>,
+}
+
+impl CategoryTree {
+    // Scan the dump and write every category with its parents and documents,
+    // returns the number of categories.
+    pub fn build(dump_path: &str, index_path: &str) -> Result<usize, Box<dyn Error>> {
+        let mut nodes: HashMap<String, CategoryNode> = HashMap::new();
+
+        for entry in DumpReader::open(dump_path)? {
+            let entry = entry?;
+            let article = &entry.article;
+
+            if article.is_redirect() {
+                continue;
+            }
+
+            let category_page = article.category_page();
+            let is_member = article.is_category_member();
+
+            if category_page.is_none() && !is_member {
+                continue;
+            }
+
+            let mut categories = article.document().categories();
+            categories.sort_unstable();
+            categories.dedup();
+
+            for category in categories.iter() {
+                let node = Self::node(&mut nodes, category);
+
+                if is_member {
+                    node.articles.push(article.title.to_owned());
+                }
+            }
+
+            if let Some(name) = category_page {
+                let node = Self::node(&mut nodes, name);
+
+                node.parents
+                    .extend(categories.into_iter().map(|x| x.to_owned()));
+            }
+        }
+
+        let mut nodes: Vec<CategoryNode> = nodes.into_values().collect();
+        nodes.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let mut writer = BufWriter::new(fs::File::create(index_path)?);
+        serde_json::to_writer(&mut writer, &nodes)?;
+        writer.flush()?;
+
+        Ok(nodes.len())
+    }
+
+    fn node<'a>(nodes: &'a mut HashMap<String, CategoryNode>, name: &str) -> &'a mut CategoryNode {
+        nodes
+            .entry(name.to_owned())
+            .or_insert_with(|| CategoryNode {
+                name: name.to_owned(),
+                ..Default::default()
+            })
+    }
+
+    pub fn load(index_path: &str) -> Result<Self, Box<dyn Error>> {
+        let mut raw = fs::read_to_string(index_path)?;
+
+        let nodes: Vec<CategoryNode> = unsafe { simd_json::from_str(&mut raw)? };
+        let mut children: HashMap<String, Vec<String>> = HashMap::new();
+
+        // nodes are sorted, so are the children
+        for node in nodes.iter() {
+            for parent in node.parents.iter() {
+                children
+                    .entry(parent.to_owned())
+                    .or_default()
+                    .push(node.name.to_owned());
+            }
+        }
+
+        Ok(CategoryTree {
+            nodes: nodes.into_iter().map(|x| (x.name.to_owned(), x)).collect(),
+            children,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    pub fn parents(&self, name: &str) -> &[String] {
+        self.nodes.get(name).map_or(&[], |x| &x.parents[..])
+    }
+
+    pub fn subcategories(&self, name: &str) -> &[String] {
+        self.children.get(name).map_or(&[], |x| &x[..])
+    }
+
+    // Documents directly in the category, in dump order.
+    pub fn articles(&self, name: &str) -> &[String] {
+        self.nodes.get(name).map_or(&[], |x| &x.articles[..])
+    }
+
+    // `name` and its subcategories down to `max_depth` levels in breadth-first order,
+    // every category is visited once even if the hierarchy has cycles.
+    pub fn descendants<'a>(&'a self, name: &'a str, max_depth: usize) -> Vec<&'a str> {
+        let mut visited: HashSet<&str> = HashSet::from([name]);
+        let mut result = vec![name];
+        let mut frontier = vec![name];
+
+        for _ in 0..max_depth {
+            let mut next = Vec::new();
+
+            for category in frontier {
+                for child in self.subcategories(category) {
+                    if visited.insert(child) {
+                        result.push(child);
+                        next.push(&child[..]);
+                    }
+                }
+            }
+
+            if next.is_empty() {
+                break;
+            }
+
+            frontier = next;
+        }
+
+        result
+    }
+
+    // Sorted titles of the documents in `name` or any of its subcategories.
+    pub fn descendant_articles(&self, name: &str, max_depth: usize) -> Vec<&str> {
+        let mut result: Vec<&str> = self
+            .descendants(name, max_depth)
+            .into_iter()
+            .flat_map(|x| self.articles(x).iter().map(|x| &x[..]))
+            .collect();
+
+        result.sort_unstable();
+        result.dedup();
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::loader::fixture::{temp_path, write_namespaced_dump};
+
+    use super::CategoryTree;
+
+    #[test]
+    fn category_tree_test() {
+        let dump_path = write_namespaced_dump(
+            "category-tree.json",
+            &[
+                (0, "하쿠레이 레이무", "[[분류:동방 프로젝트/등장인물]]"),
+                (0, "하쿠레이 신사", "[[분류:동방 프로젝트]][[분류:신사]]"),
+                (0, "동방 신사", "#redirect 하쿠레이 신사"),
+                (0, "키리사메 마리사", "[[분류:동방 프로젝트/등장인물]]"),
+                (
+                    2,
+                    "동방 프로젝트",
+                    "[[분류:게임]][[분류:동방 프로젝트/등장인물]]",
+                ),
+                (2, "동방 프로젝트/등장인물", "[[분류:동방 프로젝트]]"),
+                (2, "게임", ""),
+                (1, "동방 프로젝트", "[[분류:틀]]"),
+            ],
+        );
+        let index_path = temp_path("category-tree.json");
+
+        assert_eq!(CategoryTree::build(&dump_path, &index_path).unwrap(), 4);
+
+        let tree = CategoryTree::load(&index_path).unwrap();
+
+        assert_eq!(
+            tree.parents("동방 프로젝트"),
+            ["게임", "동방 프로젝트/등장인물"]
+        );
+        assert_eq!(tree.subcategories("게임"), ["동방 프로젝트"]);
+        assert_eq!(tree.articles("신사"), ["하쿠레이 신사"]);
+        assert!(tree.articles("틀").is_empty());
+
+        // the cycle between 동방 프로젝트 and its characters is visited once
+        assert_eq!(
+            tree.descendants("게임", 10),
+            vec!["게임", "동방 프로젝트", "동방 프로젝트/등장인물"]
+        );
+        assert_eq!(tree.descendants("게임", 1), vec!["게임", "동방 프로젝트"]);
+        assert_eq!(
+            tree.descendant_articles("게임", 10),
+            vec!["키리사메 마리사", "하쿠레이 레이무", "하쿠레이 신사"]
+        );
+        assert_eq!(tree.descendant_articles("게임", 1), vec!["하쿠레이 신사"]);
+        assert_eq!(tree.descendant_articles("게임", 0), Vec::<&str>::new());
+        assert_eq!(
+            tree.descendant_articles("없는 분류", 10),
+            Vec::<&str>::new()
+        );
+    }
+}
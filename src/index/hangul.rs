@@ -0,0 +1,343 @@
+use std::{error::Error, fs};
+
+use super::{
+    binary::{map_file, read_u32, read_u64, IndexBytes},
+    title_table::partition_point,
+};
+
+// Hangul projections of titles for as-you-type korean completion.
+//
+//   jamo     "동방" => "ㄷㅗㅇㅂㅏㅇ", compound vowels and finals are split into the
+//            letters typed on a keyboard, so "동바" is a prefix of "동방"
+//   chosung  "동방" => "ㄷㅂ", only the initial consonant of every syllable
+//
+// Characters other than Hangul are kept as they are.
+
+const SYLLABLE_BEGIN: u32 = 0xAC00;
+const SYLLABLE_END: u32 = 0xD7A3;
+
+const CHOSUNG: [char; 19] = [
+    'ㄱ', 'ㄲ', 'ㄴ', 'ㄷ', 'ㄸ', 'ㄹ', 'ㅁ', 'ㅂ', 'ㅃ', 'ㅅ', 'ㅆ', 'ㅇ', 'ㅈ', 'ㅉ', 'ㅊ', 'ㅋ',
+    'ㅌ', 'ㅍ', 'ㅎ',
+];
+
+const JUNGSUNG: [(char, &str); 21] = [
+    ('ㅏ', "ㅏ"),
+    ('ㅐ', "ㅐ"),
+    ('ㅑ', "ㅑ"),
+    ('ㅒ', "ㅒ"),
+    ('ㅓ', "ㅓ"),
+    ('ㅔ', "ㅔ"),
+    ('ㅕ', "ㅕ"),
+    ('ㅖ', "ㅖ"),
+    ('ㅗ', "ㅗ"),
+    ('ㅘ', "ㅗㅏ"),
+    ('ㅙ', "ㅗㅐ"),
+    ('ㅚ', "ㅗㅣ"),
+    ('ㅛ', "ㅛ"),
+    ('ㅜ', "ㅜ"),
+    ('ㅝ', "ㅜㅓ"),
+    ('ㅞ', "ㅜㅔ"),
+    ('ㅟ', "ㅜㅣ"),
+    ('ㅠ', "ㅠ"),
+    ('ㅡ', "ㅡ"),
+    ('ㅢ', "ㅡㅣ"),
+    ('ㅣ', "ㅣ"),
+];
+
+// index 0 (no final consonant) is not stored
+const JONGSUNG: [(char, &str); 27] = [
+    ('ㄱ', "ㄱ"),
+    ('ㄲ', "ㄲ"),
+    ('ㄳ', "ㄱㅅ"),
+    ('ㄴ', "ㄴ"),
+    ('ㄵ', "ㄴㅈ"),
+    ('ㄶ', "ㄴㅎ"),
+    ('ㄷ', "ㄷ"),
+    ('ㄹ', "ㄹ"),
+    ('ㄺ', "ㄹㄱ"),
+    ('ㄻ', "ㄹㅁ"),
+    ('ㄼ', "ㄹㅂ"),
+    ('ㄽ', "ㄹㅅ"),
+    ('ㄾ', "ㄹㅌ"),
+    ('ㄿ', "ㄹㅍ"),
+    ('ㅀ', "ㄹㅎ"),
+    ('ㅁ', "ㅁ"),
+    ('ㅂ', "ㅂ"),
+    ('ㅄ', "ㅂㅅ"),
+    ('ㅅ', "ㅅ"),
+    ('ㅆ', "ㅆ"),
+    ('ㅇ', "ㅇ"),
+    ('ㅈ', "ㅈ"),
+    ('ㅊ', "ㅊ"),
+    ('ㅋ', "ㅋ"),
+    ('ㅌ', "ㅌ"),
+    ('ㅍ', "ㅍ"),
+    ('ㅎ', "ㅎ"),
+];
+
+fn syllable_index(ch: char) -> Option<u32> {
+    let code = ch as u32;
+
+    if (SYLLABLE_BEGIN..=SYLLABLE_END).contains(&code) {
+        Some(code - SYLLABLE_BEGIN)
+    } else {
+        None
+    }
+}
+
+pub fn decompose_jamo(text: &str) -> String {
+    let mut result = String::new();
+
+    for ch in text.chars() {
+        match syllable_index(ch) {
+            Some(index) => {
+                result.push(CHOSUNG[(index / 588) as usize]);
+                result.push_str(JUNGSUNG[((index % 588) / 28) as usize].1);
+
+                if index % 28 != 0 {
+                    result.push_str(JONGSUNG[(index % 28 - 1) as usize].1);
+                }
+            }
+            None => {
+                // compound jamo typed on its own, e.g. "ㅘ"
+                match JUNGSUNG.iter().chain(JONGSUNG.iter()).find(|x| x.0 == ch) {
+                    Some((_, split)) => result.push_str(split),
+                    None => result.push(ch),
+                }
+            }
+        }
+    }
+
+    result
+}
+
+pub fn chosung(text: &str) -> String {
+    text.chars()
+        .map(|ch| match syllable_index(ch) {
+            Some(index) => CHOSUNG[(index / 588) as usize],
+            None => ch,
+        })
+        .collect()
+}
+
+// Binary hangul index layout (little-endian), the same in memory and on disk
+//
+//   magic     8 bytes "HKRHANGL"
+//   version   u32
+//   reserved  u32
+//   titles    u64, number of titles of the title index it was built from
+//   jamo      titles * 16 bytes, sorted by projection
+//             (projection offset u64, projection length u32, title id u32)
+//   chosung   titles * 16 bytes, the same for the chosung projection
+//   pool      utf-8 projections, projection offsets are relative to here
+pub const HANGUL_INDEX_MAGIC: &[u8] = b"HKRHANGL";
+const HANGUL_INDEX_VERSION: u32 = 1;
+const HEADER_SIZE: usize = 24;
+const ENTRY_SIZE: usize = 16;
+
+#[derive(Clone, Copy)]
+enum Projection {
+    Jamo = 0,
+    Chosung = 1,
+}
+
+pub struct HangulIndex {
+    bytes: IndexBytes,
+    titles: usize,
+}
+
+impl HangulIndex {
+    // `titles` are numbered by their position.
+    pub fn build<'a>(titles: impl Iterator<Item = &'a str>) -> Self {
+        let mut jamo = Vec::new();
+        let mut chosungs = Vec::new();
+
+        for (id, title) in titles.enumerate() {
+            jamo.push((decompose_jamo(title), id as u32));
+            chosungs.push((chosung(title), id as u32));
+        }
+
+        jamo.sort_unstable();
+        chosungs.sort_unstable();
+
+        let mut bytes = Vec::new();
+
+        bytes.extend_from_slice(HANGUL_INDEX_MAGIC);
+        bytes.extend_from_slice(&HANGUL_INDEX_VERSION.to_le_bytes());
+        bytes.extend_from_slice(&0u32.to_le_bytes());
+        bytes.extend_from_slice(&(jamo.len() as u64).to_le_bytes());
+
+        let mut offset = 0;
+
+        for (projection, id) in jamo.iter().chain(chosungs.iter()) {
+            bytes.extend_from_slice(&(offset as u64).to_le_bytes());
+            bytes.extend_from_slice(&(projection.len() as u32).to_le_bytes());
+            bytes.extend_from_slice(&id.to_le_bytes());
+
+            offset += projection.len();
+        }
+
+        for (projection, _) in jamo.iter().chain(chosungs.iter()) {
+            bytes.extend_from_slice(projection.as_bytes());
+        }
+
+        HangulIndex {
+            bytes: IndexBytes::Memory(bytes),
+            titles: jamo.len(),
+        }
+    }
+
+    pub fn write(&self, path: &str) -> Result<(), Box<dyn Error>> {
+        fs::write(path, &*self.bytes)?;
+
+        Ok(())
+    }
+
+    // Map an index written by `write`, it must have been built from `titles` titles.
+    pub fn open(path: &str, titles: usize) -> Result<Self, Box<dyn Error>> {
+        let bytes = map_file(path)?;
+
+        if bytes.len() < HEADER_SIZE || &bytes[..8] != HANGUL_INDEX_MAGIC {
+            return Err(format!("'{}' is not a hangul index", path).into());
+        }
+
+        let version = read_u32(&bytes, 8);
+        if version != HANGUL_INDEX_VERSION {
+            return Err(format!(
+                "Unsupported hangul index version {} (expected {}), build it again",
+                version, HANGUL_INDEX_VERSION
+            )
+            .into());
+        }
+
+        let index = HangulIndex {
+            titles: read_u64(&bytes, 16) as usize,
+            bytes: IndexBytes::Mapped(bytes),
+        };
+
+        if index.titles != titles {
+            return Err(format!(
+                "Hangul index '{}' has {} titles, the title index has {}",
+                path, index.titles, titles
+            )
+            .into());
+        }
+
+        if index.bytes.len() < index.pool() {
+            return Err(format!("Hangul index '{}' is truncated", path).into());
+        }
+
+        for projection in [Projection::Jamo, Projection::Chosung] {
+            for i in 0..titles {
+                let (offset, len) = index.span(projection, i);
+
+                if offset + len > index.bytes.len() || index.id(projection, i) >= titles as u32 {
+                    return Err(
+                        format!("Hangul index '{}' has a broken entry at {}", path, i).into(),
+                    );
+                }
+            }
+        }
+
+        Ok(index)
+    }
+
+    // Number of titles the index was built from.
+    pub fn titles(&self) -> usize {
+        self.titles
+    }
+
+    fn pool(&self) -> usize {
+        HEADER_SIZE + self.titles * 2 * ENTRY_SIZE
+    }
+
+    fn entry(&self, projection: Projection, i: usize) -> usize {
+        HEADER_SIZE + (projection as usize * self.titles + i) * ENTRY_SIZE
+    }
+
+    fn span(&self, projection: Projection, i: usize) -> (usize, usize) {
+        let entry = self.entry(projection, i);
+
+        (
+            self.pool() + read_u64(&self.bytes, entry) as usize,
+            read_u32(&self.bytes, entry + 8) as usize,
+        )
+    }
+
+    fn id(&self, projection: Projection, i: usize) -> u32 {
+        read_u32(&self.bytes, self.entry(projection, i) + 12)
+    }
+
+    // projections are compared as bytes, which orders them like strings
+    fn projection(&self, projection: Projection, i: usize) -> &[u8] {
+        let (offset, len) = self.span(projection, i);
+
+        &self.bytes[offset..offset + len]
+    }
+
+    // Sorted ids of titles whose jamo decomposition starts with the one of `what`.
+    pub fn find_jamo_prefix(&self, what: &str) -> Vec<u32> {
+        self.find_prefix(Projection::Jamo, &decompose_jamo(what))
+    }
+
+    // Sorted ids of titles whose initial consonants start with `what`.
+    // Syllables in `what` are reduced to their initial consonant too.
+    pub fn find_chosung(&self, what: &str) -> Vec<u32> {
+        self.find_prefix(Projection::Chosung, &chosung(what))
+    }
+
+    fn find_prefix(&self, projection: Projection, prefix: &str) -> Vec<u32> {
+        let prefix = prefix.as_bytes();
+        let start = partition_point(0..self.titles, |i| self.projection(projection, i) < prefix);
+        let end = partition_point(start..self.titles, |i| {
+            self.projection(projection, i).starts_with(prefix)
+        });
+
+        let mut result: Vec<u32> = (start..end).map(|i| self.id(projection, i)).collect();
+        result.sort_unstable();
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::loader::fixture::temp_path;
+
+    use super::{chosung, decompose_jamo, HangulIndex};
+
+    #[test]
+    fn hangul_projection_test() {
+        assert_eq!(decompose_jamo("동방"), "ㄷㅗㅇㅂㅏㅇ");
+        assert_eq!(decompose_jamo("과자"), "ㄱㅗㅏㅈㅏ");
+        assert_eq!(decompose_jamo("닭 A1"), "ㄷㅏㄹㄱ A1");
+        assert_eq!(decompose_jamo("ㅘ"), "ㅗㅏ");
+        assert_eq!(chosung("동방프로젝트"), "ㄷㅂㅍㄹㅈㅌ");
+        assert_eq!(chosung("동방 Project"), "ㄷㅂ Project");
+    }
+
+    #[test]
+    fn hangul_index_test() {
+        let titles = ["동방프로젝트", "동방 신사", "동물", "다리", "서든어택"];
+        let index = HangulIndex::build(titles.iter().copied());
+
+        assert_eq!(index.find_chosung("ㄷㅂ"), vec![0, 1]);
+        assert_eq!(index.find_chosung("ㄷㅂㅍㄹㅈㅌ"), vec![0]);
+        assert_eq!(index.find_chosung("동ㅁ"), vec![2]);
+        assert_eq!(index.find_jamo_prefix("동바"), vec![0, 1]);
+        assert_eq!(index.find_jamo_prefix("달"), vec![3]);
+        assert_eq!(index.find_jamo_prefix("섣"), vec![4]);
+        assert!(index.find_jamo_prefix("원신").is_empty());
+
+        // the mapped index answers the same
+        let path = temp_path("hangul-index.bin");
+        index.write(&path).unwrap();
+
+        let mapped = HangulIndex::open(&path, titles.len()).unwrap();
+
+        assert_eq!(mapped.titles(), 5);
+        assert_eq!(mapped.find_chosung("ㄷㅂ"), vec![0, 1]);
+        assert_eq!(mapped.find_jamo_prefix("동바"), vec![0, 1]);
+        assert!(HangulIndex::open(&path, titles.len() + 1).is_err());
+    }
+}
@@ -0,0 +1,447 @@
+use std::{
+    borrow::Cow,
+    collections::{hash_map::Entry, HashMap, HashSet},
+    error::Error,
+    fs,
+    io::{BufWriter, Write},
+};
+
+use memmap2::Mmap;
+
+use crate::loader::load::DumpReader;
+
+use super::{
+    binary::{map_file, read_u32, read_u64, write_u32, write_u64},
+    title::TitleIndex,
+};
+
+// Link graph between articles.
+//
+// Nodes are the ids of the title index the graph was built with, which are the positions of
+// the titles in sorted order. Link targets have their anchor stripped, relative links
+// (`[[/하위 문서]]`, `[[../]]`) are made absolute and redirects are resolved through
+// `TitleIndex::get`, so an edge always points to the article a reader lands on.
+// Links to missing articles are dropped and redirect documents have no outlinks.
+//
+// Every occurrence of a link is an edge, `[[A]] ... [[A]]` links to `A` twice.
+
+// Binary link index layout (little-endian)
+//
+//   magic      8 bytes "HKRLINKS"
+//   version    u32
+//   reserved   u32
+//   nodes      u64, the number of titles in the title index
+//   edges      u64
+//   out        (nodes + 1) * u64 offsets, then edges * u32 targets in document order
+//   in         (nodes + 1) * u64 offsets, then edges * u32 sources in id order
+pub const LINK_INDEX_MAGIC: &[u8] = b"HKRLINKS";
+const LINK_INDEX_VERSION: u32 = 1;
+const HEADER_SIZE: usize = 32;
+
+pub struct LinkIndex {
+    mmap: Mmap,
+    nodes: usize,
+    edges: usize,
+    outgoing: usize,
+    incoming: usize,
+}
+
+impl LinkIndex {
+    // Scan the dump and write the link graph, returns the number of edges.
+    pub fn build(
+        title_index: &TitleIndex,
+        dump_path: &str,
+        index_path: &str,
+    ) -> Result<usize, Box<dyn Error>> {
+        let mut edges: Vec<(u32, u32)> = Vec::new();
+        let mut resolved: HashMap<String, Option<u32>> = HashMap::new();
+
+        for entry in DumpReader::open(dump_path)? {
+            let article = entry?.article;
+
+            if article.is_redirect() {
+                continue;
+            }
+
+            let source = match title_index.id(&article.title) {
+                Some(id) => id as u32,
+                None => continue,
+            };
+
+            for target in article.document().links() {
+                let target = absolute_target(&article.title, target);
+
+                if !resolved.contains_key(target.as_ref()) {
+                    let id = title_index
+                        .get(&target)
+                        .and_then(|x| title_index.id(&x.title))
+                        .map(|x| x as u32);
+                    resolved.insert(target.to_string(), id);
+                }
+
+                if let Some(id) = resolved[target.as_ref()] {
+                    edges.push((source, id));
+                }
+            }
+        }
+
+        Self::write(index_path, title_index.len(), edges)
+    }
+
+    // Write a graph of `nodes` nodes from `(source, target)` edges, returns the number of edges.
+    pub fn write(
+        path: &str,
+        nodes: usize,
+        mut edges: Vec<(u32, u32)>,
+    ) -> Result<usize, Box<dyn Error>> {
+        let mut writer = BufWriter::new(fs::File::create(path)?);
+
+        writer.write_all(LINK_INDEX_MAGIC)?;
+        write_u32(&mut writer, LINK_INDEX_VERSION)?;
+        write_u32(&mut writer, 0)?;
+        write_u64(&mut writer, nodes as u64)?;
+        write_u64(&mut writer, edges.len() as u64)?;
+
+        // stable, targets of a source stay in document order
+        edges.sort_by_key(|x| x.0);
+        Self::write_adjacency(&mut writer, nodes, edges.iter().map(|x| (x.0, x.1)))?;
+
+        edges.sort_by_key(|x| (x.1, x.0));
+        Self::write_adjacency(&mut writer, nodes, edges.iter().map(|x| (x.1, x.0)))?;
+
+        writer.flush()?;
+
+        Ok(edges.len())
+    }
+
+    // `edges` are `(node, neighbour)` pairs grouped by node.
+    fn write_adjacency(
+        writer: &mut impl Write,
+        nodes: usize,
+        edges: impl Iterator<Item = (u32, u32)> + Clone,
+    ) -> Result<(), Box<dyn Error>> {
+        let mut degrees = vec![0u64; nodes];
+
+        for (node, _) in edges.clone() {
+            degrees[node as usize] += 1;
+        }
+
+        let mut offset = 0;
+        write_u64(writer, 0)?;
+
+        for degree in degrees {
+            offset += degree;
+            write_u64(writer, offset)?;
+        }
+
+        for (_, neighbour) in edges {
+            write_u32(writer, neighbour)?;
+        }
+
+        Ok(())
+    }
+
+    // The index must have been built with the same title index, node ids are title ids.
+    pub fn open(index_path: &str, title_index: &TitleIndex) -> Result<Self, Box<dyn Error>> {
+        Self::open_nodes(index_path, title_index.len())
+    }
+
+    // Open a graph that must have exactly `expected` nodes.
+    pub fn open_nodes(index_path: &str, expected: usize) -> Result<Self, Box<dyn Error>> {
+        let mmap = map_file(index_path)?;
+
+        if mmap.len() < HEADER_SIZE || &mmap[..8] != LINK_INDEX_MAGIC {
+            return Err(format!("'{}' is not a link index", index_path).into());
+        }
+
+        let version = read_u32(&mmap, 8);
+        if version != LINK_INDEX_VERSION {
+            return Err(format!(
+                "Unsupported link index version {} (expected {}), build it again",
+                version, LINK_INDEX_VERSION
+            )
+            .into());
+        }
+
+        let nodes = read_u64(&mmap, 16) as usize;
+        let edges = read_u64(&mmap, 24) as usize;
+
+        if nodes != expected {
+            return Err(format!(
+                "Link index '{}' has {} nodes but the title index has {} titles, build it again",
+                index_path, nodes, expected
+            )
+            .into());
+        }
+
+        let adjacency_size = (nodes + 1) * 8 + edges * 4;
+
+        if mmap.len() < HEADER_SIZE + adjacency_size * 2 {
+            return Err(format!("Link index '{}' is truncated", index_path).into());
+        }
+
+        Ok(LinkIndex {
+            mmap,
+            nodes,
+            edges,
+            outgoing: HEADER_SIZE,
+            incoming: HEADER_SIZE + adjacency_size,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes == 0
+    }
+
+    pub fn edges(&self) -> usize {
+        self.edges
+    }
+
+    fn adjacency(&self, base: usize, id: usize) -> impl Iterator<Item = u32> + '_ {
+        let (start, end) = if id < self.nodes {
+            (
+                read_u64(&self.mmap, base + id * 8) as usize,
+                read_u64(&self.mmap, base + (id + 1) * 8) as usize,
+            )
+        } else {
+            (0, 0)
+        };
+        let neighbours = base + (self.nodes + 1) * 8;
+
+        (start..end).map(move |i| read_u32(&self.mmap, neighbours + i * 4))
+    }
+
+    // Every link of `id` in document order, repeated links are kept.
+    pub fn out_edges(&self, id: usize) -> impl Iterator<Item = u32> + '_ {
+        self.adjacency(self.outgoing, id)
+    }
+
+    // The source of every link to `id` in id order, repeated links are kept.
+    pub fn in_edges(&self, id: usize) -> impl Iterator<Item = u32> + '_ {
+        self.adjacency(self.incoming, id)
+    }
+
+    // Distinct articles `id` links to, in the order they are first linked.
+    pub fn outlinks(&self, id: usize) -> Vec<u32> {
+        let mut seen = HashSet::new();
+
+        self.out_edges(id).filter(|x| seen.insert(*x)).collect()
+    }
+
+    // Distinct articles linking to `id`, in id order.
+    pub fn backlinks(&self, id: usize) -> Vec<u32> {
+        let mut result: Vec<u32> = self.in_edges(id).collect();
+        result.dedup();
+        result
+    }
+
+    // Shortest chain of links from `from` to `to` with at most `max_depth` links, both ends
+    // included. Searches forward over outlinks and backward over backlinks, a level at a time
+    // from the side with the smaller frontier.
+    pub fn shortest_path(&self, from: usize, to: usize, max_depth: usize) -> Option<Vec<u32>> {
+        let (from, to) = (from as u32, to as u32);
+
+        if from == to {
+            return Some(vec![from]);
+        }
+
+        // node => the node it was reached from
+        let mut forward: HashMap<u32, u32> = HashMap::from([(from, from)]);
+        let mut backward: HashMap<u32, u32> = HashMap::from([(to, to)]);
+        let mut forward_frontier = vec![from];
+        let mut backward_frontier = vec![to];
+
+        for _ in 0..max_depth {
+            if forward_frontier.is_empty() || backward_frontier.is_empty() {
+                return None;
+            }
+
+            let is_forward = forward_frontier.len() <= backward_frontier.len();
+            let (frontier, visited, other) = if is_forward {
+                (&mut forward_frontier, &mut forward, &backward)
+            } else {
+                (&mut backward_frontier, &mut backward, &forward)
+            };
+
+            let mut next = Vec::new();
+
+            for node in frontier.iter() {
+                let neighbours = if is_forward {
+                    self.outlinks(*node as usize)
+                } else {
+                    self.backlinks(*node as usize)
+                };
+
+                for neighbour in neighbours {
+                    if let Entry::Vacant(entry) = visited.entry(neighbour) {
+                        entry.insert(*node);
+
+                        if other.contains_key(&neighbour) {
+                            return Some(Self::join_path(&forward, &backward, neighbour));
+                        }
+
+                        next.push(neighbour);
+                    }
+                }
+            }
+
+            *frontier = next;
+        }
+
+        None
+    }
+
+    fn join_path(forward: &HashMap<u32, u32>, backward: &HashMap<u32, u32>, meet: u32) -> Vec<u32> {
+        let mut path = vec![meet];
+
+        while forward[path.last().unwrap()] != *path.last().unwrap() {
+            path.push(forward[path.last().unwrap()]);
+        }
+
+        path.reverse();
+
+        while backward[path.last().unwrap()] != *path.last().unwrap() {
+            path.push(backward[path.last().unwrap()]);
+        }
+
+        path
+    }
+}
+
+// `[[/하위]]` in `문서` points to `문서/하위`, `[[../]]` to the parent document
+// and `[[../형제]]` to `형제` under the parent document.
+fn absolute_target<'a>(source: &str, target: &'a str) -> Cow<'a, str> {
+    if let Some(rest) = target.strip_prefix("../") {
+        let parent = match source.rfind('/') {
+            Some(i) => &source[..i],
+            None => source,
+        };
+
+        if rest.is_empty() {
+            Cow::Owned(parent.to_owned())
+        } else {
+            Cow::Owned(format!("{}/{}", parent, rest))
+        }
+    } else if target.starts_with('/') {
+        Cow::Owned(format!("{}{}", source, target))
+    } else {
+        Cow::Borrowed(target)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        index::title::TitleIndex,
+        loader::fixture::{temp_path, write_dump},
+    };
+
+    use super::{absolute_target, LinkIndex};
+
+    #[test]
+    fn link_target_test() {
+        assert_eq!(absolute_target("동방", "레이무"), "레이무");
+        assert_eq!(absolute_target("동방", "/설정"), "동방/설정");
+        assert_eq!(absolute_target("동방/설정", "../"), "동방");
+        assert_eq!(absolute_target("동방/설정", "../등장인물"), "동방/등장인물");
+    }
+
+    #[test]
+    fn link_index_test() {
+        let dump_path = write_dump(
+            "link.json",
+            &[
+                (
+                    "동방프로젝트",
+                    "[[하쿠레이 레이무|레이무]]와 [[마리사#개요]], [[/설정]]. [[레이무]] [[없는 문서]]",
+                ),
+                ("레이무", "#redirect 하쿠레이 레이무"),
+                ("하쿠레이 레이무", "[[동방프로젝트]]의 주인공. [[마리사]]와 친하다."),
+                ("마리사", "[[하쿠레이 레이무]]"),
+                ("동방프로젝트/설정", "[[../]]"),
+            ],
+        );
+        let title_path = temp_path("link-title-index.json");
+        let index_path = temp_path("link-index.bin");
+
+        TitleIndex::build(&dump_path, &title_path).unwrap();
+        let title_index = TitleIndex::load(&dump_path, &title_path).unwrap();
+
+        // 동방프로젝트, 동방프로젝트/설정, 레이무, 마리사, 하쿠레이 레이무
+        let id = |x| title_index.id(x).unwrap();
+
+        assert_eq!(
+            LinkIndex::build(&title_index, &dump_path, &index_path).unwrap(),
+            8
+        );
+
+        let index = LinkIndex::open(&index_path, &title_index).unwrap();
+        let titles = |ids: Vec<u32>| {
+            ids.into_iter()
+                .map(|x| title_index.title(x as usize).to_owned())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(index.len(), 5);
+        assert_eq!(index.edges(), 8);
+        assert_eq!(
+            titles(index.outlinks(id("동방프로젝트"))),
+            vec!["하쿠레이 레이무", "마리사", "동방프로젝트/설정"]
+        );
+        assert_eq!(index.out_edges(id("동방프로젝트")).count(), 4);
+        assert!(index.outlinks(id("레이무")).is_empty());
+        assert_eq!(
+            titles(index.backlinks(id("하쿠레이 레이무"))),
+            vec!["동방프로젝트", "마리사"]
+        );
+        assert_eq!(index.in_edges(id("하쿠레이 레이무")).count(), 3);
+        assert_eq!(
+            titles(index.backlinks(id("동방프로젝트"))),
+            vec!["동방프로젝트/설정", "하쿠레이 레이무"]
+        );
+        assert!(index.backlinks(id("레이무")).is_empty());
+    }
+
+    #[test]
+    fn link_path_test() {
+        // A → B, C; B → D; C → D, E; D → F; E → A; G → A
+        let edges = vec![
+            (0, 1),
+            (0, 2),
+            (1, 3),
+            (2, 3),
+            (2, 4),
+            (3, 5),
+            (4, 0),
+            (6, 0),
+        ];
+        let index_path = temp_path("link-path-index.bin");
+
+        assert_eq!(LinkIndex::write(&index_path, 7, edges).unwrap(), 8);
+        assert!(LinkIndex::open_nodes(&index_path, 6).is_err());
+
+        let index = LinkIndex::open_nodes(&index_path, 7).unwrap();
+        let id = |x: &str| (x.as_bytes()[0] - b'A') as usize;
+        let path = |from, to, depth| {
+            index.shortest_path(id(from), id(to), depth).map(|x| {
+                x.into_iter()
+                    .map(|x| ((b'A' + x as u8) as char).to_string())
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            })
+        };
+
+        assert_eq!(path("A", "A", 0), Some("A".to_owned()));
+        assert_eq!(path("A", "B", 1), Some("A B".to_owned()));
+        assert_eq!(path("A", "F", 3), Some("A B D F".to_owned()));
+        assert_eq!(path("A", "F", 2), None);
+        assert_eq!(path("E", "F", 6), Some("E A B D F".to_owned()));
+        assert_eq!(path("C", "B", 6), Some("C E A B".to_owned()));
+        assert_eq!(path("F", "A", 6), None);
+        assert_eq!(path("A", "G", 6), None);
+    }
+}
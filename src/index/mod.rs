@@ -1,2 +1,12 @@
+pub mod binary;
+pub mod bktree;
+pub mod body;
 pub mod category;
+pub mod category_tree;
+pub mod hangul;
+pub mod link;
+pub mod ngram;
+pub mod rank;
+pub mod redirect;
 pub mod title;
+pub mod title_table;
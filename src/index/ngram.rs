@@ -0,0 +1,283 @@
+use std::{collections::HashMap, error::Error, fs};
+
+use super::{
+    binary::{map_file, read_u32, read_u64, IndexBytes},
+    title_table::partition_point,
+};
+
+// Inverted index from character n-grams to the ids of the titles containing them.
+// Bigrams over unicode scalars are used so a Hangul syllable is a single character,
+// single character queries fall back to the unigram postings.
+
+// Binary n-gram index layout (little-endian), the same in memory and on disk
+//
+//   magic     8 bytes "HKRNGRAM"
+//   version   u32
+//   reserved  u32
+//   titles    u64, number of titles of the title index it was built from
+//   grams     u64
+//   grams     grams * 24 bytes, sorted by (first, second)
+//             (first char u32, second char u32 or `UNIGRAM`, postings offset u64,
+//              postings length u64, in ids)
+//   postings  u32 title ids, sorted per gram
+pub const NGRAM_INDEX_MAGIC: &[u8] = b"HKRNGRAM";
+const NGRAM_INDEX_VERSION: u32 = 1;
+const HEADER_SIZE: usize = 32;
+const GRAM_SIZE: usize = 24;
+
+// second char of a unigram, above every unicode scalar
+const UNIGRAM: u32 = u32::MAX;
+
+pub struct NgramIndex {
+    bytes: IndexBytes,
+    titles: usize,
+    grams: usize,
+}
+
+impl NgramIndex {
+    // `titles` are numbered by their position.
+    pub fn build<'a>(titles: impl Iterator<Item = &'a str>) -> Self {
+        let mut grams: HashMap<(u32, u32), Vec<u32>> = HashMap::new();
+        let mut count = 0;
+
+        for (id, title) in titles.enumerate() {
+            let id = id as u32;
+            let chars: Vec<u32> = title.chars().map(|x| x as u32).collect();
+
+            for ch in &chars {
+                push_posting(grams.entry((*ch, UNIGRAM)).or_default(), id);
+            }
+
+            for w in chars.windows(2) {
+                push_posting(grams.entry((w[0], w[1])).or_default(), id);
+            }
+
+            count += 1;
+        }
+
+        let mut grams: Vec<((u32, u32), Vec<u32>)> = grams.into_iter().collect();
+        grams.sort_unstable_by_key(|x| x.0);
+
+        let mut bytes = Vec::new();
+
+        bytes.extend_from_slice(NGRAM_INDEX_MAGIC);
+        bytes.extend_from_slice(&NGRAM_INDEX_VERSION.to_le_bytes());
+        bytes.extend_from_slice(&0u32.to_le_bytes());
+        bytes.extend_from_slice(&(count as u64).to_le_bytes());
+        bytes.extend_from_slice(&(grams.len() as u64).to_le_bytes());
+
+        let mut offset = 0;
+
+        for ((first, second), postings) in grams.iter() {
+            bytes.extend_from_slice(&first.to_le_bytes());
+            bytes.extend_from_slice(&second.to_le_bytes());
+            bytes.extend_from_slice(&(offset as u64).to_le_bytes());
+            bytes.extend_from_slice(&(postings.len() as u64).to_le_bytes());
+
+            offset += postings.len();
+        }
+
+        for (_, postings) in grams.iter() {
+            for id in postings {
+                bytes.extend_from_slice(&id.to_le_bytes());
+            }
+        }
+
+        NgramIndex {
+            bytes: IndexBytes::Memory(bytes),
+            titles: count,
+            grams: grams.len(),
+        }
+    }
+
+    pub fn write(&self, path: &str) -> Result<(), Box<dyn Error>> {
+        fs::write(path, &*self.bytes)?;
+
+        Ok(())
+    }
+
+    // Map an index written by `write`, it must have been built from `titles` titles.
+    pub fn open(path: &str, titles: usize) -> Result<Self, Box<dyn Error>> {
+        let bytes = map_file(path)?;
+
+        if bytes.len() < HEADER_SIZE || &bytes[..8] != NGRAM_INDEX_MAGIC {
+            return Err(format!("'{}' is not an n-gram index", path).into());
+        }
+
+        let version = read_u32(&bytes, 8);
+        if version != NGRAM_INDEX_VERSION {
+            return Err(format!(
+                "Unsupported n-gram index version {} (expected {}), build it again",
+                version, NGRAM_INDEX_VERSION
+            )
+            .into());
+        }
+
+        let index = NgramIndex {
+            titles: read_u64(&bytes, 16) as usize,
+            grams: read_u64(&bytes, 24) as usize,
+            bytes: IndexBytes::Mapped(bytes),
+        };
+
+        if index.titles != titles {
+            return Err(format!(
+                "N-gram index '{}' has {} titles, the title index has {}",
+                path, index.titles, titles
+            )
+            .into());
+        }
+
+        let postings = HEADER_SIZE + index.grams * GRAM_SIZE;
+
+        if index.bytes.len() < postings
+            || (0..index.grams).any(|i| {
+                let (offset, len) = index.postings_span(i);
+                offset + len * 4 > index.bytes.len()
+            })
+        {
+            return Err(format!("N-gram index '{}' is truncated", path).into());
+        }
+
+        Ok(index)
+    }
+
+    // Number of titles the index was built from.
+    pub fn titles(&self) -> usize {
+        self.titles
+    }
+
+    fn gram(&self, i: usize) -> (u32, u32) {
+        let entry = HEADER_SIZE + i * GRAM_SIZE;
+
+        (
+            read_u32(&self.bytes, entry),
+            read_u32(&self.bytes, entry + 4),
+        )
+    }
+
+    fn postings_span(&self, i: usize) -> (usize, usize) {
+        let entry = HEADER_SIZE + i * GRAM_SIZE;
+
+        (
+            HEADER_SIZE + self.grams * GRAM_SIZE + read_u64(&self.bytes, entry + 8) as usize * 4,
+            read_u64(&self.bytes, entry + 16) as usize,
+        )
+    }
+
+    fn postings(&self, gram: (u32, u32)) -> Option<Vec<u32>> {
+        let i = partition_point(0..self.grams, |i| self.gram(i) < gram);
+
+        if i == self.grams || self.gram(i) != gram {
+            return None;
+        }
+
+        let (offset, len) = self.postings_span(i);
+
+        Some(
+            (0..len)
+                .map(|k| read_u32(&self.bytes, offset + k * 4))
+                .collect(),
+        )
+    }
+
+    // Sorted ids of titles that contain every n-gram of `what`.
+    // Candidates must still be verified, `None` means every title is a candidate.
+    pub fn candidates(&self, what: &str) -> Option<Vec<u32>> {
+        let chars: Vec<u32> = what.chars().map(|x| x as u32).collect();
+
+        let grams: Vec<(u32, u32)> = match chars.len() {
+            0 => return None,
+            1 => vec![(chars[0], UNIGRAM)],
+            _ => chars.windows(2).map(|w| (w[0], w[1])).collect(),
+        };
+
+        let mut postings = Vec::new();
+
+        for gram in grams {
+            match self.postings(gram) {
+                Some(p) => postings.push(p),
+                None => return Some(Vec::new()),
+            }
+        }
+
+        postings.sort_by_key(|x| x.len());
+
+        let mut result = postings[0].clone();
+
+        for p in &postings[1..] {
+            if result.is_empty() {
+                break;
+            }
+
+            result = intersect(&result, p);
+        }
+
+        Some(result)
+    }
+}
+
+// A title with a repeated n-gram is pushed once.
+fn push_posting(posting: &mut Vec<u32>, id: u32) {
+    if posting.last() != Some(&id) {
+        posting.push(id);
+    }
+}
+
+fn intersect(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut result = Vec::new();
+    let (mut i, mut j) = (0, 0);
+
+    while i < a.len() && j < b.len() {
+        if a[i] < b[j] {
+            i += 1;
+        } else if a[i] > b[j] {
+            j += 1;
+        } else {
+            result.push(a[i]);
+            i += 1;
+            j += 1;
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::loader::fixture::temp_path;
+
+    use super::NgramIndex;
+
+    #[test]
+    fn ngram_candidates_test() {
+        let titles = [
+            "동방프로젝트",
+            "동방 신사",
+            "하쿠레이 신사",
+            "방동",
+            "서든어택",
+        ];
+        let index = NgramIndex::build(titles.iter().copied());
+
+        assert_eq!(index.candidates("동방"), Some(vec![0, 1]));
+        assert_eq!(index.candidates("신사"), Some(vec![1, 2]));
+        assert_eq!(index.candidates("동"), Some(vec![0, 1, 3]));
+        assert_eq!(index.candidates("방 신"), Some(vec![1]));
+        assert_eq!(index.candidates("없는"), Some(vec![]));
+        assert_eq!(index.candidates("없"), Some(vec![]));
+        assert_eq!(index.candidates(""), None);
+
+        // the mapped index answers the same
+        let path = temp_path("ngram-index.bin");
+        index.write(&path).unwrap();
+
+        let mapped = NgramIndex::open(&path, titles.len()).unwrap();
+
+        assert_eq!(mapped.titles(), 5);
+        assert_eq!(mapped.candidates("동방"), Some(vec![0, 1]));
+        assert_eq!(mapped.candidates("동"), Some(vec![0, 1, 3]));
+        assert_eq!(mapped.candidates("없는"), Some(vec![]));
+        // built from another title index
+        assert!(NgramIndex::open(&path, titles.len() + 1).is_err());
+    }
+}
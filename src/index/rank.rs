@@ -0,0 +1,223 @@
+use std::{
+    error::Error,
+    fs,
+    io::{BufWriter, Write},
+};
+
+use memmap2::Mmap;
+
+use super::{
+    binary::{map_file, read_u32, read_u64, write_u32, write_u64},
+    link::LinkIndex,
+    title::TitleIndex,
+};
+
+// Centrality of every article in the link graph.
+//
+// PageRank runs over the distinct outlinks of every article, self links are ignored. The rank
+// of articles without outlinks (including redirect documents) is spread over every article.
+// Degrees count distinct articles as `outlinks` and `backlinks` do, self links included.
+
+// Binary rank index layout (little-endian)
+//
+//   magic     8 bytes "HKRRANK\0"
+//   version   u32
+//   reserved  u32
+//   nodes     u64, the number of titles in the title index
+//   scores    nodes * 16 bytes, in title id order
+//             (pagerank f64, in-degree u32, out-degree u32)
+pub const RANK_INDEX_MAGIC: &[u8] = b"HKRRANK\0";
+const RANK_INDEX_VERSION: u32 = 1;
+const HEADER_SIZE: usize = 24;
+const NODE_SIZE: usize = 16;
+
+const DAMPING: f64 = 0.85;
+const MAX_ITERATIONS: usize = 100;
+// stop once the scores move less than this in total
+const TOLERANCE: f64 = 1e-9;
+
+pub struct RankIndex {
+    mmap: Mmap,
+    nodes: usize,
+}
+
+impl RankIndex {
+    // Compute the scores of the link graph and write them, returns the number of nodes.
+    pub fn build(link_index: &LinkIndex, index_path: &str) -> Result<usize, Box<dyn Error>> {
+        let scores = pagerank(link_index);
+        let mut writer = BufWriter::new(fs::File::create(index_path)?);
+
+        writer.write_all(RANK_INDEX_MAGIC)?;
+        write_u32(&mut writer, RANK_INDEX_VERSION)?;
+        write_u32(&mut writer, 0)?;
+        write_u64(&mut writer, scores.len() as u64)?;
+
+        for (id, score) in scores.iter().enumerate() {
+            writer.write_all(&score.to_le_bytes())?;
+            write_u32(&mut writer, link_index.backlinks(id).len() as u32)?;
+            write_u32(&mut writer, link_index.outlinks(id).len() as u32)?;
+        }
+
+        writer.flush()?;
+
+        Ok(scores.len())
+    }
+
+    // The index must have been built with the same title index, node ids are title ids.
+    pub fn open(index_path: &str, title_index: &TitleIndex) -> Result<Self, Box<dyn Error>> {
+        Self::open_nodes(index_path, title_index.len())
+    }
+
+    // Open an index that must have exactly `expected` nodes.
+    pub fn open_nodes(index_path: &str, expected: usize) -> Result<Self, Box<dyn Error>> {
+        let mmap = map_file(index_path)?;
+
+        if mmap.len() < HEADER_SIZE || &mmap[..8] != RANK_INDEX_MAGIC {
+            return Err(format!("'{}' is not a rank index", index_path).into());
+        }
+
+        let version = read_u32(&mmap, 8);
+        if version != RANK_INDEX_VERSION {
+            return Err(format!(
+                "Unsupported rank index version {} (expected {}), build it again",
+                version, RANK_INDEX_VERSION
+            )
+            .into());
+        }
+
+        let nodes = read_u64(&mmap, 16) as usize;
+
+        if nodes != expected {
+            return Err(format!(
+                "Rank index '{}' has {} nodes but the title index has {} titles, build it again",
+                index_path, nodes, expected
+            )
+            .into());
+        }
+
+        if mmap.len() < HEADER_SIZE + nodes * NODE_SIZE {
+            return Err(format!("Rank index '{}' is truncated", index_path).into());
+        }
+
+        Ok(RankIndex { mmap, nodes })
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes == 0
+    }
+
+    // PageRank of `id`, the scores of every node sum to 1.
+    pub fn score(&self, id: usize) -> f64 {
+        f64::from_bits(read_u64(&self.mmap, HEADER_SIZE + id * NODE_SIZE))
+    }
+
+    // Number of distinct articles linking to `id`.
+    pub fn in_degree(&self, id: usize) -> u32 {
+        read_u32(&self.mmap, HEADER_SIZE + id * NODE_SIZE + 8)
+    }
+
+    // Number of distinct articles `id` links to.
+    pub fn out_degree(&self, id: usize) -> u32 {
+        read_u32(&self.mmap, HEADER_SIZE + id * NODE_SIZE + 12)
+    }
+}
+
+// Power iteration over the distinct, non-self outlinks of every node.
+pub fn pagerank(link_index: &LinkIndex) -> Vec<f64> {
+    let nodes = link_index.len();
+
+    if nodes == 0 {
+        return Vec::new();
+    }
+
+    let outlinks: Vec<Vec<u32>> = (0..nodes)
+        .map(|id| {
+            let mut links = link_index.outlinks(id);
+            links.retain(|x| *x as usize != id);
+            links
+        })
+        .collect();
+
+    let base = 1.0 / nodes as f64;
+    let mut scores = vec![base; nodes];
+
+    for _ in 0..MAX_ITERATIONS {
+        let dangling: f64 = outlinks
+            .iter()
+            .zip(scores.iter())
+            .filter(|(links, _)| links.is_empty())
+            .map(|(_, score)| score)
+            .sum();
+
+        let mut next = vec![(1.0 - DAMPING + DAMPING * dangling) * base; nodes];
+
+        for (links, score) in outlinks.iter().zip(scores.iter()) {
+            if links.is_empty() {
+                continue;
+            }
+
+            let share = DAMPING * score / links.len() as f64;
+
+            for target in links {
+                next[*target as usize] += share;
+            }
+        }
+
+        let delta: f64 = next
+            .iter()
+            .zip(scores.iter())
+            .map(|(x, y)| (x - y).abs())
+            .sum();
+
+        scores = next;
+
+        if delta < TOLERANCE {
+            break;
+        }
+    }
+
+    scores
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{index::link::LinkIndex, loader::fixture::temp_path};
+
+    use super::RankIndex;
+
+    #[test]
+    fn rank_index_test() {
+        // 0 동방프로젝트, 1 하쿠레이 레이무, 2 키리사메 마리사, 3 서든어택, 4 레이무 (a redirect)
+        let edges = vec![(0, 1), (0, 2), (1, 0), (1, 1), (2, 1), (2, 1), (3, 1)];
+        let link_path = temp_path("rank-link-index.bin");
+        let index_path = temp_path("rank-index.bin");
+
+        LinkIndex::write(&link_path, 5, edges).unwrap();
+        let link_index = LinkIndex::open_nodes(&link_path, 5).unwrap();
+
+        assert_eq!(RankIndex::build(&link_index, &index_path).unwrap(), 5);
+        assert!(RankIndex::open_nodes(&index_path, 4).is_err());
+
+        let index = RankIndex::open_nodes(&index_path, 5).unwrap();
+
+        let total: f64 = (0..index.len()).map(|x| index.score(x)).sum();
+        assert!((total - 1.0).abs() < 1e-6);
+
+        let mut ranked: Vec<usize> = (0..index.len()).collect();
+        ranked.sort_by(|a, b| index.score(*b).total_cmp(&index.score(*a)));
+
+        assert_eq!(ranked[..2], [1, 0]);
+        assert!(index.score(2) > index.score(3));
+        assert_eq!(index.score(3), index.score(4));
+
+        // self links are counted as degrees, not as rank
+        assert_eq!(index.in_degree(1), 4);
+        assert_eq!(index.out_degree(1), 2);
+        assert_eq!(index.out_degree(2), 1);
+        assert_eq!(index.in_degree(4), 0);
+    }
+}
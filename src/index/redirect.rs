@@ -0,0 +1,318 @@
+use std::{
+    collections::{HashMap, HashSet},
+    error::Error,
+    fs,
+    io::{BufWriter, Write},
+};
+
+use memmap2::Mmap;
+
+use crate::loader::load::DumpReader;
+
+use super::{
+    binary::{map_file, read_u32, read_u64, write_u32, write_u64},
+    title::TitleIndex,
+    title_table::partition_point,
+};
+
+// Final target of every redirect document.
+//
+// Chains (`A -> B -> C`) are followed to the first document which is not a redirect. A redirect
+// is broken when the chain ends at a missing document and cyclic when it comes back to a
+// document of the chain, neither has a target. Anchors of redirect targets are ignored.
+
+// Binary redirect table layout (little-endian)
+//
+//   magic      8 bytes "HKRREDIR"
+//   version    u32
+//   reserved   u32
+//   nodes      u64, the number of titles in the title index
+//   redirects  u64
+//   sources    redirects * 12 bytes, sorted by source
+//              (source u32, target u32, hops u16, flags u16)
+//   targets    redirects * u32 positions in the source table, sorted by target and source
+pub const REDIRECT_TABLE_MAGIC: &[u8] = b"HKRREDIR";
+const REDIRECT_TABLE_VERSION: u32 = 1;
+const HEADER_SIZE: usize = 32;
+const ENTRY_SIZE: usize = 12;
+
+const FLAG_BROKEN: u32 = 1;
+const FLAG_CYCLIC: u32 = 2;
+const NO_TARGET: u32 = u32::MAX;
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Redirect {
+    pub source: u32,
+    // `None` for broken and cyclic redirects
+    pub target: Option<u32>,
+    // number of redirects followed, 1 for a direct redirect
+    pub hops: u32,
+    pub broken: bool,
+    pub cyclic: bool,
+}
+
+pub struct RedirectTable {
+    mmap: Mmap,
+    redirects: usize,
+}
+
+impl RedirectTable {
+    // Scan the dump and write the final target of every redirect, returns the number of
+    // redirects.
+    pub fn build(
+        title_index: &TitleIndex,
+        dump_path: &str,
+        index_path: &str,
+    ) -> Result<usize, Box<dyn Error>> {
+        let mut targets: HashMap<String, String> = HashMap::new();
+
+        for entry in DumpReader::open(dump_path)? {
+            let article = entry?.article;
+
+            if article.is_redirect() {
+                let target = article.get_redirect().to_owned();
+                targets.insert(article.title, target);
+            }
+        }
+
+        let mut entries: Vec<(u32, u32, u32, u32)> = Vec::new();
+
+        for source in targets.keys() {
+            let id = match title_index.id(source) {
+                Some(id) => id as u32,
+                None => continue,
+            };
+
+            let mut chain: HashSet<&str> = HashSet::from([&source[..]]);
+            let mut current = &targets[source];
+            let mut hops = 1;
+
+            let (target, flags) = loop {
+                if !chain.insert(current) {
+                    break (NO_TARGET, FLAG_CYCLIC);
+                }
+
+                match targets.get(current) {
+                    Some(next) => {
+                        current = next;
+                        hops += 1;
+                    }
+                    None => match title_index.id(current) {
+                        Some(target) => break (target as u32, 0),
+                        None => break (NO_TARGET, FLAG_BROKEN),
+                    },
+                }
+            };
+
+            entries.push((id, target, hops, flags));
+        }
+
+        entries.sort_unstable();
+
+        let mut by_target: Vec<u32> = (0..entries.len() as u32).collect();
+        by_target.sort_by_key(|i| (entries[*i as usize].1, entries[*i as usize].0));
+
+        let mut writer = BufWriter::new(fs::File::create(index_path)?);
+
+        writer.write_all(REDIRECT_TABLE_MAGIC)?;
+        write_u32(&mut writer, REDIRECT_TABLE_VERSION)?;
+        write_u32(&mut writer, 0)?;
+        write_u64(&mut writer, title_index.len() as u64)?;
+        write_u64(&mut writer, entries.len() as u64)?;
+
+        for (source, target, hops, flags) in entries.iter() {
+            write_u32(&mut writer, *source)?;
+            write_u32(&mut writer, *target)?;
+            write_u32(&mut writer, (*hops).min(u16::MAX as u32) | (flags << 16))?;
+        }
+
+        for i in by_target {
+            write_u32(&mut writer, i)?;
+        }
+
+        writer.flush()?;
+
+        Ok(entries.len())
+    }
+
+    // The table must have been built with the same title index, ids are title ids.
+    pub fn open(index_path: &str, title_index: &TitleIndex) -> Result<Self, Box<dyn Error>> {
+        let mmap = map_file(index_path)?;
+
+        if mmap.len() < HEADER_SIZE || &mmap[..8] != REDIRECT_TABLE_MAGIC {
+            return Err(format!("'{}' is not a redirect table", index_path).into());
+        }
+
+        let version = read_u32(&mmap, 8);
+        if version != REDIRECT_TABLE_VERSION {
+            return Err(format!(
+                "Unsupported redirect table version {} (expected {}), build it again",
+                version, REDIRECT_TABLE_VERSION
+            )
+            .into());
+        }
+
+        let nodes = read_u64(&mmap, 16) as usize;
+        let redirects = read_u64(&mmap, 24) as usize;
+
+        if nodes != title_index.len() {
+            return Err(format!(
+                "Redirect table '{}' has {} nodes but the title index has {} titles, build it again",
+                index_path,
+                nodes,
+                title_index.len()
+            )
+            .into());
+        }
+
+        if mmap.len() < HEADER_SIZE + redirects * (ENTRY_SIZE + 4) {
+            return Err(format!("Redirect table '{}' is truncated", index_path).into());
+        }
+
+        Ok(RedirectTable { mmap, redirects })
+    }
+
+    pub fn len(&self) -> usize {
+        self.redirects
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.redirects == 0
+    }
+
+    fn entry(&self, i: usize) -> Redirect {
+        let offset = HEADER_SIZE + i * ENTRY_SIZE;
+        let target = read_u32(&self.mmap, offset + 4);
+        let packed = read_u32(&self.mmap, offset + 8);
+
+        Redirect {
+            source: read_u32(&self.mmap, offset),
+            target: if target == NO_TARGET {
+                None
+            } else {
+                Some(target)
+            },
+            hops: packed & 0xffff,
+            broken: (packed >> 16) & FLAG_BROKEN != 0,
+            cyclic: (packed >> 16) & FLAG_CYCLIC != 0,
+        }
+    }
+
+    fn source(&self, i: usize) -> u32 {
+        read_u32(&self.mmap, HEADER_SIZE + i * ENTRY_SIZE)
+    }
+
+    fn by_target(&self, k: usize) -> usize {
+        read_u32(
+            &self.mmap,
+            HEADER_SIZE + self.redirects * ENTRY_SIZE + k * 4,
+        ) as usize
+    }
+
+    // The redirect of the document `id`, `None` if it is not a redirect.
+    pub fn get(&self, id: usize) -> Option<Redirect> {
+        let id = id as u32;
+        let i = partition_point(0..self.redirects, |i| self.source(i) < id);
+
+        if i < self.redirects && self.source(i) == id {
+            Some(self.entry(i))
+        } else {
+            None
+        }
+    }
+
+    // Sorted ids of the redirects resolving to `id`, through chains too.
+    pub fn aliases(&self, id: usize) -> Vec<u32> {
+        let id = id as u32;
+        let target = |k| self.entry(self.by_target(k)).target.unwrap_or(NO_TARGET);
+        let start = partition_point(0..self.redirects, |k| target(k) < id);
+
+        (start..self.redirects)
+            .take_while(|k| target(*k) == id)
+            .map(|k| self.source(self.by_target(k)))
+            .collect()
+    }
+
+    // Broken and cyclic redirects in id order.
+    pub fn unresolved(&self) -> impl Iterator<Item = Redirect> + '_ {
+        let start = partition_point(0..self.redirects, |k| {
+            self.entry(self.by_target(k)).target.is_some()
+        });
+
+        (start..self.redirects).map(|k| self.entry(self.by_target(k)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        index::title::TitleIndex,
+        loader::fixture::{temp_path, write_dump},
+    };
+
+    use super::{Redirect, RedirectTable};
+
+    #[test]
+    fn redirect_table_test() {
+        let dump_path = write_dump(
+            "redirect.json",
+            &[
+                ("하쿠레이 레이무", "[[동방프로젝트]]의 주인공."),
+                ("레이무", "#redirect 하쿠레이 레이무"),
+                ("영몽", "#redirect 레이무"),
+                ("博麗霊夢", "#넘겨주기 영몽#s-1"),
+                ("마리사", "#redirect 없는 문서"),
+                ("A", "#redirect B"),
+                ("B", "#redirect A"),
+            ],
+        );
+        let title_path = temp_path("redirect-title-index.json");
+        let index_path = temp_path("redirect-table.bin");
+
+        TitleIndex::build(&dump_path, &title_path).unwrap();
+        let mut title_index = TitleIndex::load(&dump_path, &title_path).unwrap();
+        let id = |x| title_index.id(x).unwrap() as u32;
+
+        assert_eq!(
+            RedirectTable::build(&title_index, &dump_path, &index_path).unwrap(),
+            6
+        );
+
+        let table = RedirectTable::open(&index_path, &title_index).unwrap();
+
+        assert_eq!(
+            table.get(id("博麗霊夢") as usize),
+            Some(Redirect {
+                source: id("博麗霊夢"),
+                target: Some(id("하쿠레이 레이무")),
+                hops: 3,
+                broken: false,
+                cyclic: false,
+            })
+        );
+        assert!(table.get(id("하쿠레이 레이무") as usize).is_none());
+        assert!(table.get(id("마리사") as usize).unwrap().broken);
+        assert!(table.get(id("A") as usize).unwrap().cyclic);
+        let mut aliases = vec![id("博麗霊夢"), id("레이무"), id("영몽")];
+        aliases.sort();
+
+        assert_eq!(table.aliases(id("하쿠레이 레이무") as usize), aliases);
+        assert_eq!(table.unresolved().count(), 3);
+
+        // the table answers `get` and cycles end without it too
+        assert!(title_index.get("A").is_none());
+        assert!(title_index.get("마리사").is_none());
+
+        title_index.set_redirect_table(table);
+
+        assert_eq!(
+            title_index.get("博麗霊夢").unwrap().title,
+            "하쿠레이 레이무"
+        );
+        assert!(title_index.get("A").is_none());
+        assert_eq!(
+            title_index.get("하쿠레이 레이무").unwrap().title,
+            "하쿠레이 레이무"
+        );
+    }
+}
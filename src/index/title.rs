@@ -1,16 +1,31 @@
-use std::{
-    cmp::Ordering,
-    collections::HashMap,
-    error::Error,
-    fs,
-    io::{Read, Seek, SeekFrom},
+use std::{cell::OnceCell, collections::HashMap, error::Error, fs, path::Path};
+
+use crate::{
+    loader::load::{read_entry, DumpReader},
+    model::{article::Article, namespace::Namespace},
+};
+
+use super::{
+    binary::has_magic,
+    bktree::BkTree,
+    hangul::{self, HangulIndex},
+    ngram::NgramIndex,
+    redirect::RedirectTable,
+    title_table::{MappedTitleTable, MemoryTitleTable, TitleTable, TITLE_TABLE_MAGIC},
 };
 
-use crate::model::article::Article;
+// Redirects `get` follows without a redirect table.
+const MAX_REDIRECT_HOPS: usize = 16;
 
 pub struct TitleIndex {
-    map: HashMap<String, Vec<usize>>,
+    table: Box<dyn TitleTable>,
+    ngram: OnceCell<NgramIndex>,
+    hangul: OnceCell<HangulIndex>,
+    fuzzy: OnceCell<BkTree>,
+    redirects: Option<RedirectTable>,
     file: fs::File,
+    dump_path: String,
+    index_path: String,
 }
 
 pub enum TitleIndexFindOption {
@@ -18,71 +33,642 @@ pub enum TitleIndexFindOption {
     Contains,
     StartsWith,
     EndsWith,
+    Chosung,
+    JamoPrefix,
 }
 
 impl TitleIndex {
-    pub fn load(dump_path: &str, index_path: &str) -> Result<Self, Box<dyn Error>> {
-        let mut raw = fs::read_to_string(index_path)?;
+    // Scan the dump and write `title -> [start, end]` byte ranges of every article. A title
+    // found twice in the dump is an error, only one of the articles could be looked up.
+    pub fn build(dump_path: &str, index_path: &str) -> Result<usize, Box<dyn Error>> {
+        let mut map: HashMap<String, Vec<usize>> = HashMap::new();
+        let mut duplicates: Vec<String> = Vec::new();
+
+        for entry in DumpReader::open(dump_path)? {
+            let entry = entry?;
+            let title = entry.article.title;
+
+            if map.contains_key(&title) {
+                duplicates.push(title);
+                continue;
+            }
+
+            map.insert(title, vec![entry.start, entry.end]);
+        }
+
+        if !duplicates.is_empty() {
+            return Err(format!(
+                "{} titles appear more than once in the dump: {}",
+                duplicates.len(),
+                duplicates.join(", ")
+            )
+            .into());
+        }
+
+        fs::write(index_path, serde_json::to_string_pretty(&map)?)?;
+
+        Ok(map.len())
+    }
+
+    // Convert `title-index.json` to the binary, memory-mappable format.
+    pub fn convert(json_path: &str, binary_path: &str) -> Result<usize, Box<dyn Error>> {
+        let map = Self::load_json(json_path)?;
+
+        let mut entries: Vec<(&str, usize, usize)> = map
+            .iter()
+            .map(|(title, range)| (&title[..], range[0], range[1]))
+            .collect();
+
+        MappedTitleTable::write(binary_path, &mut entries)
+    }
+
+    // Write the n-gram index of `title:contains` next to the title index, returns the number of
+    // indexed titles.
+    pub fn build_ngram(index_path: &str, ngram_path: &str) -> Result<usize, Box<dyn Error>> {
+        let table = Self::load_table(index_path)?;
+
+        NgramIndex::build((0..table.len()).map(|i| table.title(i))).write(ngram_path)?;
+
+        Ok(table.len())
+    }
+
+    // Write the BK-tree of `title:fuzzy` next to the title index, returns the number of indexed
+    // titles.
+    pub fn build_fuzzy(index_path: &str, fuzzy_path: &str) -> Result<usize, Box<dyn Error>> {
+        let table = Self::load_table(index_path)?;
+
+        BkTree::build(table.len(), |i| table.title(i)).write(fuzzy_path)?;
+
+        Ok(table.len())
+    }
 
-        unsafe {
-            Ok(TitleIndex {
-                map: simd_json::from_str(&mut raw)?,
-                file: fs::File::open(dump_path)?,
+    // Write the jamo and chosung projections of `title:jamo_prefix` and `title:chosung` next to
+    // the title index, returns the number of indexed titles.
+    pub fn build_hangul(index_path: &str, hangul_path: &str) -> Result<usize, Box<dyn Error>> {
+        let table = Self::load_table(index_path)?;
+
+        HangulIndex::build((0..table.len()).map(|i| table.title(i))).write(hangul_path)?;
+
+        Ok(table.len())
+    }
+
+    // The projections are opened, or built without their file, on the first hangul query.
+    fn hangul(&self) -> &HangulIndex {
+        self.hangul.get_or_init(|| {
+            self.open_or_build("hangul", HangulIndex::open, || {
+                HangulIndex::build(self.keys())
             })
+        })
+    }
+
+    // `title-index.json` and `title-index.bin` => `title-index.<name>.bin`, the lookup indexes of
+    // both formats are the same.
+    pub fn companion_path(index_path: &str, name: &str) -> String {
+        let stem = index_path
+            .strip_suffix(".json")
+            .or_else(|| index_path.strip_suffix(".bin"))
+            .unwrap_or(index_path);
+
+        format!("{}.{}.bin", stem, name)
+    }
+
+    // Open either `title-index.json` or a binary index created by `convert`.
+    // Both are kept sorted by title, the binary index is queried in place.
+    pub fn load(dump_path: &str, index_path: &str) -> Result<Self, Box<dyn Error>> {
+        Ok(TitleIndex {
+            table: Self::load_table(index_path)?,
+            ngram: OnceCell::new(),
+            hangul: OnceCell::new(),
+            fuzzy: OnceCell::new(),
+            redirects: None,
+            file: fs::File::open(dump_path)?,
+            dump_path: dump_path.to_owned(),
+            index_path: index_path.to_owned(),
+        })
+    }
+
+    fn load_table(index_path: &str) -> Result<Box<dyn TitleTable>, Box<dyn Error>> {
+        if has_magic(index_path, TITLE_TABLE_MAGIC) {
+            return Ok(Box::new(MappedTitleTable::open(index_path)?));
         }
+
+        let map = Self::load_json(index_path)?;
+
+        Ok(Box::new(MemoryTitleTable::from(
+            map.iter()
+                .map(|(title, range)| (&title[..], range[0], range[1]))
+                .collect(),
+        )))
     }
 
-    pub fn get_no_redirect(&self, key: &str) -> Option<Article> {
-        if !self.map.contains_key(key) {
-            return None;
+    // Open the lookup index written next to the title index, or build it in memory without it.
+    // An index built from other titles is left out.
+    fn open_or_build<T>(
+        &self,
+        name: &str,
+        open: impl FnOnce(&str, usize) -> Result<T, Box<dyn Error>>,
+        build: impl FnOnce() -> T,
+    ) -> T {
+        let path = Self::companion_path(&self.index_path, name);
+
+        if !Path::new(&path).exists() {
+            return build();
+        }
+
+        match open(&path, self.len()) {
+            Ok(index) => index,
+            Err(e) => {
+                eprintln!(
+                    "warning: {} is not used, build it again with `hakurei index title-{}`: {}",
+                    path, name, e
+                );
+                build()
+            }
         }
+    }
 
-        let index = &self.map[key];
+    // Resolve redirects with a table built by `RedirectTable::build` instead of reading every
+    // document of the chain.
+    pub fn set_redirect_table(&mut self, table: RedirectTable) {
+        self.redirects = Some(table);
+    }
 
-        let offset_start = index[0];
-        let offset_end = index[1];
+    pub fn redirect_table(&self) -> Option<&RedirectTable> {
+        self.redirects.as_ref()
+    }
 
-        let mut file = self.file.try_clone().unwrap();
-        file.seek(SeekFrom::Start(offset_start as u64)).unwrap();
+    fn load_json(index_path: &str) -> Result<HashMap<String, Vec<usize>>, Box<dyn Error>> {
+        let mut raw = fs::read_to_string(index_path)?;
+
+        unsafe { Ok(simd_json::from_str(&mut raw)?) }
+    }
+
+    // The dump the byte ranges point into, body queries stream it.
+    pub fn dump_path(&self) -> &str {
+        &self.dump_path
+    }
+
+    pub fn len(&self) -> usize {
+        self.table.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.table.is_empty()
+    }
+
+    // All titles in sorted order.
+    pub fn keys(&self) -> impl Iterator<Item = &str> + '_ {
+        (0..self.table.len()).map(|i| self.table.title(i))
+    }
+
+    // Position of `key` in sorted order, graph indexes use it as the node id.
+    pub fn id(&self, key: &str) -> Option<usize> {
+        self.table.find(key)
+    }
+
+    pub fn title(&self, id: usize) -> &str {
+        self.table.title(id)
+    }
+
+    fn range(&self, key: &str) -> Option<(usize, usize)> {
+        self.table.find(key).map(|i| self.table.range(i))
+    }
+
+    // Check that every entry points to the article with the same title.
+    pub fn verify(&self) -> Result<usize, Box<dyn Error>> {
+        for key in self.keys() {
+            match self.get_no_redirect(key) {
+                Some(article) if article.title == key => {}
+                Some(article) => {
+                    return Err(format!(
+                        "Title index entry '{}' points to article '{}'",
+                        key, article.title
+                    )
+                    .into())
+                }
+                None => {
+                    return Err(
+                        format!("Title index entry '{}' does not point to an article", key).into(),
+                    )
+                }
+            }
+        }
 
-        let mut buf = vec![0; offset_end - offset_start + 1];
-        file.read(&mut buf).unwrap();
+        Ok(self.len())
+    }
 
-        let raw = String::from_utf8(buf).unwrap();
+    pub fn get_no_redirect(&self, key: &str) -> Option<Article> {
+        let (offset_start, offset_end) = self.range(key)?;
 
-        Some(serde_json::from_str(&raw).unwrap())
+        read_entry(&self.file, offset_start, offset_end).ok()
     }
 
+    // The document `key` ends at, `None` for missing documents and for broken or cyclic
+    // redirects. Without a redirect table chains longer than `MAX_REDIRECT_HOPS` are broken.
     pub fn get(&self, key: &str) -> Option<Article> {
-        if !self.map.contains_key(key) {
-            return None;
+        if let (Some(table), Some(id)) = (&self.redirects, self.id(key)) {
+            if let Some(redirect) = table.get(id) {
+                return self.get_no_redirect(self.title(redirect.target? as usize));
+            }
         }
 
-        match self.get_no_redirect(key) {
-            Some(x) => {
-                if x.is_redirect() {
-                    self.get(x.get_redirect())
-                } else {
-                    Some(x)
+        let mut article = self.get_no_redirect(key)?;
+
+        for _ in 0..MAX_REDIRECT_HOPS {
+            if !article.is_redirect() {
+                return Some(article);
+            }
+
+            article = self.get_no_redirect(article.get_redirect())?;
+        }
+
+        None
+    }
+
+    // Matching titles in sorted order.
+    pub fn find_by(&self, what: &str, option: TitleIndexFindOption) -> Vec<&str> {
+        let table = &self.table;
+
+        match option {
+            TitleIndexFindOption::Extact => table
+                .find(what)
+                .map(|i| table.title(i))
+                .into_iter()
+                .collect(),
+            // The n-gram index is opened, or built without its file, on the first query. Then
+            // only the titles sharing every n-gram with the query are verified.
+            TitleIndexFindOption::Contains => {
+                let ngram = self.ngram.get_or_init(|| {
+                    self.open_or_build("ngram", NgramIndex::open, || NgramIndex::build(self.keys()))
+                });
+
+                match ngram.candidates(what) {
+                    Some(candidates) => candidates
+                        .into_iter()
+                        .map(|i| table.title(i as usize))
+                        .filter(|x| x.contains(what))
+                        .collect(),
+                    None => self.keys().filter(|x| x.contains(what)).collect(),
                 }
             }
-            None => None,
+            TitleIndexFindOption::StartsWith => {
+                table.find_prefix(what).map(|i| table.title(i)).collect()
+            }
+            TitleIndexFindOption::EndsWith => table
+                .find_suffix(what)
+                .into_iter()
+                .map(|i| table.title(i))
+                .collect(),
+            TitleIndexFindOption::Chosung => self
+                .hangul()
+                .find_chosung(what)
+                .into_iter()
+                .map(|i| table.title(i as usize))
+                .collect(),
+            TitleIndexFindOption::JamoPrefix => self
+                .hangul()
+                .find_jamo_prefix(what)
+                .into_iter()
+                .map(|i| table.title(i as usize))
+                .collect(),
+        }
+    }
+
+    // Matching titles of one namespace in sorted order, `what` is matched against titles without
+    // the namespace prefix. Namespaces with a prefix are a sorted range of the index.
+    pub fn find_by_namespace(
+        &self,
+        what: &str,
+        option: TitleIndexFindOption,
+        namespace: Namespace,
+    ) -> Vec<&str> {
+        let prefix = match namespace.prefix() {
+            Some(prefix) => format!("{}:", prefix),
+            None => {
+                return self
+                    .find_by(what, option)
+                    .into_iter()
+                    .filter(|x| Namespace::split(x).0 == namespace)
+                    .collect()
+            }
+        };
+
+        let names = || {
+            self.table
+                .find_prefix(&prefix)
+                .map(|i| self.table.title(i))
+                .map(|x| (x, &x[prefix.len()..]))
+        };
+
+        match option {
+            TitleIndexFindOption::Extact | TitleIndexFindOption::StartsWith => {
+                self.find_by(&format!("{}{}", prefix, what), option)
+            }
+            TitleIndexFindOption::Contains => names()
+                .filter(|(_, name)| name.contains(what))
+                .map(|(x, _)| x)
+                .collect(),
+            TitleIndexFindOption::EndsWith => names()
+                .filter(|(_, name)| name.ends_with(what))
+                .map(|(x, _)| x)
+                .collect(),
+            TitleIndexFindOption::Chosung => {
+                let what = hangul::chosung(what);
+                names()
+                    .filter(|(_, name)| hangul::chosung(name).starts_with(&what))
+                    .map(|(x, _)| x)
+                    .collect()
+            }
+            TitleIndexFindOption::JamoPrefix => {
+                let what = hangul::decompose_jamo(what);
+                names()
+                    .filter(|(_, name)| hangul::decompose_jamo(name).starts_with(&what))
+                    .map(|(x, _)| x)
+                    .collect()
+            }
         }
     }
 
-    pub fn find_by(&self, what: &str, option: TitleIndexFindOption) -> Vec<&String> {
-        self.map
-            .keys()
-            .filter(|x| match option {
-                TitleIndexFindOption::Extact => what.cmp(x) == Ordering::Equal,
-                TitleIndexFindOption::Contains => x.contains(what),
-                TitleIndexFindOption::StartsWith => x.starts_with(what),
-                TitleIndexFindOption::EndsWith => x.ends_with(what),
+    // Titles within `max_distance` edits of `what`, ranked by distance and then by title.
+    // The BK-tree is opened, or built without its file, on the first fuzzy query.
+    pub fn find_fuzzy(&self, what: &str, max_distance: usize) -> Vec<(&str, usize)> {
+        let table = &self.table;
+        let tree = self.fuzzy.get_or_init(|| {
+            self.open_or_build("fuzzy", BkTree::open, || {
+                BkTree::build(table.len(), |i| table.title(i))
             })
-            .collect()
+        });
+
+        let mut result: Vec<(&str, usize)> = tree
+            .find(what, max_distance, |i| table.title(i))
+            .into_iter()
+            .map(|(i, distance)| (table.title(i), distance))
+            .collect();
+
+        result.sort_by(|a, b| a.1.cmp(&b.1).then(a.0.cmp(b.0)));
+        result
+    }
+
+    pub fn find_one_by(&self, what: &str) -> Option<&str> {
+        self.table.find(what).map(|i| self.table.title(i))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        loader::fixture::{temp_path, write_dump, write_namespaced_dump},
+        model::namespace::Namespace,
+    };
+
+    use super::{TitleIndex, TitleIndexFindOption};
+
+    #[test]
+    fn title_index_build_test() {
+        let dump_path = write_dump(
+            "title-build.json",
+            &[
+                ("하쿠레이 신사", "[[분류:장소]]"),
+                ("\"따옴표\" 문서", "{\"title\":\"가짜\"}"),
+                ("博麗神社", "#redirect 하쿠레이 신사"),
+            ],
+        );
+        let index_path = temp_path("title-build-index.json");
+
+        assert_eq!(TitleIndex::build(&dump_path, &index_path).unwrap(), 3);
+
+        let index = TitleIndex::load(&dump_path, &index_path).unwrap();
+
+        assert_eq!(index.verify().unwrap(), 3);
+        assert_eq!(
+            index.get_no_redirect("\"따옴표\" 문서").unwrap().text,
+            "{\"title\":\"가짜\"}"
+        );
+        assert_eq!(index.get("博麗神社").unwrap().title, "하쿠레이 신사");
+        assert!(index.get_no_redirect("가짜").is_none());
+
+        let dump_path = write_dump(
+            "title-duplicate.json",
+            &[("하쿠레이 신사", "첫째"), ("하쿠레이 신사", "둘째")],
+        );
+
+        assert!(TitleIndex::build(&dump_path, &temp_path("title-duplicate-index.json")).is_err());
     }
 
-    pub fn find_one_by(&self, what: &str) -> Option<&String> {
-        self.map.keys().find(|x| what.cmp(x) == Ordering::Equal)
+    #[test]
+    fn title_index_namespace_test() {
+        let dump_path = write_namespaced_dump(
+            "title-namespace.json",
+            &[
+                (0, "동방 프로젝트", "[[분류:동방 프로젝트]]"),
+                (2, "동방 프로젝트", "[[분류:게임]]"),
+                (1, "동방 프로젝트", "{{{#!wiki}}}"),
+                (1, "틀:동방 프로젝트/등장인물", ""),
+                (0, "틀린 그림 찾기", ""),
+            ],
+        );
+        let index_path = temp_path("title-namespace-index.json");
+
+        // the same title in other namespaces is another article
+        assert_eq!(TitleIndex::build(&dump_path, &index_path).unwrap(), 5);
+
+        let index = TitleIndex::load(&dump_path, &index_path).unwrap();
+        let find = |what, option, namespace| index.find_by_namespace(what, option, namespace);
+
+        assert_eq!(index.verify().unwrap(), 5);
+        assert_eq!(
+            index.get("분류:동방 프로젝트").unwrap().namespace,
+            Namespace::Category
+        );
+        assert_eq!(
+            find(
+                "동방",
+                TitleIndexFindOption::StartsWith,
+                Namespace::Template
+            ),
+            vec!["틀:동방 프로젝트", "틀:동방 프로젝트/등장인물"]
+        );
+        assert_eq!(
+            find(
+                "프로젝트",
+                TitleIndexFindOption::EndsWith,
+                Namespace::Template
+            ),
+            vec!["틀:동방 프로젝트"]
+        );
+        assert_eq!(
+            find("틀", TitleIndexFindOption::Contains, Namespace::Template),
+            Vec::<&str>::new()
+        );
+        assert_eq!(
+            find("ㄷㅂ", TitleIndexFindOption::Chosung, Namespace::Category),
+            vec!["분류:동방 프로젝트"]
+        );
+        assert_eq!(
+            find("틀", TitleIndexFindOption::StartsWith, Namespace::Document),
+            vec!["틀린 그림 찾기"]
+        );
+        assert_eq!(
+            find("동방", TitleIndexFindOption::Contains, Namespace::Document),
+            vec!["동방 프로젝트"]
+        );
+    }
+
+    #[test]
+    fn title_index_convert_test() {
+        let dump_path = write_dump(
+            "title-convert.json",
+            &[
+                ("하쿠레이 신사", "[[분류:장소]]"),
+                ("博麗神社", "#redirect 하쿠레이 신사"),
+            ],
+        );
+        let json_path = temp_path("title-convert-index.json");
+        let binary_path = temp_path("title-convert-index.bin");
+
+        TitleIndex::build(&dump_path, &json_path).unwrap();
+        assert_eq!(TitleIndex::convert(&json_path, &binary_path).unwrap(), 2);
+
+        let index = TitleIndex::load(&dump_path, &binary_path).unwrap();
+
+        assert_eq!(index.verify().unwrap(), 2);
+        assert_eq!(index.find_one_by("博麗神社"), Some("博麗神社"));
+        assert_eq!(index.get("博麗神社").unwrap().title, "하쿠레이 신사");
+        assert_eq!(
+            index.find_by("신사", TitleIndexFindOption::EndsWith),
+            vec!["하쿠레이 신사"]
+        );
+    }
+
+    #[test]
+    fn title_index_ngram_test() {
+        let dump_path = write_dump(
+            "title-ngram.json",
+            &[
+                ("동방프로젝트", ""),
+                ("동방 신사", ""),
+                ("방동방", ""),
+                ("동 방", ""),
+            ],
+        );
+        let index_path = temp_path("title-ngram-index.json");
+
+        TitleIndex::build(&dump_path, &index_path).unwrap();
+
+        let index = TitleIndex::load(&dump_path, &index_path).unwrap();
+        let scanned: Vec<&str> = index.keys().filter(|x| x.contains("동방")).collect();
+
+        assert_eq!(
+            index.find_by("동방", TitleIndexFindOption::Contains),
+            scanned
+        );
+        assert_eq!(scanned, vec!["동방 신사", "동방프로젝트", "방동방"]);
+        assert_eq!(
+            index.find_by("방동방", TitleIndexFindOption::Contains),
+            vec!["방동방"]
+        );
+
+        // the index written next to the title index is mapped instead
+        let ngram_path = TitleIndex::companion_path(&index_path, "ngram");
+
+        assert_eq!(
+            TitleIndex::build_ngram(&index_path, &ngram_path).unwrap(),
+            4
+        );
+
+        let index = TitleIndex::load(&dump_path, &index_path).unwrap();
+
+        assert_eq!(
+            index.find_by("동방", TitleIndexFindOption::Contains),
+            vec!["동방 신사", "동방프로젝트", "방동방"]
+        );
+        assert_eq!(index.ngram.get().unwrap().titles(), 4);
+    }
+
+    #[test]
+    fn title_index_fuzzy_test() {
+        let dump_path = write_dump(
+            "title-fuzzy.json",
+            &[
+                ("동방프로젝트", ""),
+                ("동방 프로젝트", ""),
+                ("동방프로잭트", ""),
+                ("서든어택", ""),
+            ],
+        );
+        let index_path = temp_path("title-fuzzy-index.json");
+
+        TitleIndex::build(&dump_path, &index_path).unwrap();
+
+        let index = TitleIndex::load(&dump_path, &index_path).unwrap();
+
+        assert_eq!(
+            index.find_fuzzy("동방프로젝트", 2),
+            vec![
+                ("동방프로젝트", 0),
+                ("동방 프로젝트", 1),
+                ("동방프로잭트", 1)
+            ]
+        );
+        assert!(index.find_fuzzy("원신", 1).is_empty());
+
+        // the tree written next to the title index is mapped instead
+        let fuzzy_path = TitleIndex::companion_path(&index_path, "fuzzy");
+
+        assert_eq!(
+            TitleIndex::build_fuzzy(&index_path, &fuzzy_path).unwrap(),
+            4
+        );
+
+        let index = TitleIndex::load(&dump_path, &index_path).unwrap();
+
+        assert_eq!(
+            index.find_fuzzy("동방프로잭트", 1),
+            vec![("동방프로잭트", 0), ("동방프로젝트", 1)]
+        );
+        assert_eq!(index.fuzzy.get().unwrap().titles(), 4);
+    }
+
+    #[test]
+    fn title_index_hangul_test() {
+        let dump_path = write_dump(
+            "title-hangul.json",
+            &[
+                ("동방프로젝트", ""),
+                ("동방 신사", ""),
+                ("동물", ""),
+                ("다리", ""),
+            ],
+        );
+        let index_path = temp_path("title-hangul-index.json");
+
+        TitleIndex::build(&dump_path, &index_path).unwrap();
+
+        let index = TitleIndex::load(&dump_path, &index_path).unwrap();
+
+        assert_eq!(
+            index.find_by("ㄷㅂ", TitleIndexFindOption::Chosung),
+            vec!["동방 신사", "동방프로젝트"]
+        );
+        assert_eq!(
+            index.find_by("달", TitleIndexFindOption::JamoPrefix),
+            vec!["다리"]
+        );
+
+        // the projections written next to the title index are mapped instead
+        let hangul_path = TitleIndex::companion_path(&index_path, "hangul");
+
+        assert_eq!(
+            TitleIndex::build_hangul(&index_path, &hangul_path).unwrap(),
+            4
+        );
+
+        let index = TitleIndex::load(&dump_path, &index_path).unwrap();
+
+        assert_eq!(
+            index.find_by("동바", TitleIndexFindOption::JamoPrefix),
+            vec!["동방 신사", "동방프로젝트"]
+        );
+        assert_eq!(index.hangul.get().unwrap().titles(), 4);
     }
 }
@@ -0,0 +1,339 @@
+use std::{
+    cmp::Ordering,
+    error::Error,
+    fs,
+    io::{BufWriter, Write},
+    ops::Range,
+    str,
+};
+
+use memmap2::Mmap;
+
+use super::binary::{map_file, read_u32, read_u64, write_u32, write_u64};
+
+// Titles sorted in byte order, plus a permutation of the same entries sorted by
+// their reversed titles, so prefix and suffix lookups are both range scans.
+pub trait TitleTable {
+    fn len(&self) -> usize;
+    fn title(&self, i: usize) -> &str;
+    fn range(&self, i: usize) -> (usize, usize);
+    // index of the `k`-th entry in reversed title order
+    fn reversed(&self, k: usize) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn find(&self, title: &str) -> Option<usize> {
+        let i = partition_point(0..self.len(), |i| self.title(i) < title);
+
+        if i < self.len() && self.title(i) == title {
+            Some(i)
+        } else {
+            None
+        }
+    }
+
+    fn find_prefix(&self, prefix: &str) -> Range<usize> {
+        let start = partition_point(0..self.len(), |i| self.title(i) < prefix);
+        let end = partition_point(start..self.len(), |i| self.title(i).starts_with(prefix));
+
+        start..end
+    }
+
+    // sorted indices of titles ending with `suffix`
+    fn find_suffix(&self, suffix: &str) -> Vec<usize> {
+        let start = partition_point(0..self.len(), |k| {
+            cmp_reversed(self.title(self.reversed(k)), suffix) == Ordering::Less
+        });
+        let end = partition_point(start..self.len(), |k| {
+            self.title(self.reversed(k)).ends_with(suffix)
+        });
+
+        let mut result: Vec<usize> = (start..end).map(|k| self.reversed(k)).collect();
+        result.sort_unstable();
+        result
+    }
+}
+
+pub fn cmp_reversed(a: &str, b: &str) -> Ordering {
+    a.chars().rev().cmp(b.chars().rev())
+}
+
+// First index in `range` where `pred` is false, `pred` must be partitioned.
+pub fn partition_point(range: Range<usize>, pred: impl Fn(usize) -> bool) -> usize {
+    let (mut lo, mut hi) = (range.start, range.end);
+
+    while lo < hi {
+        let mid = lo + (hi - lo) / 2;
+
+        if pred(mid) {
+            lo = mid + 1;
+        } else {
+            hi = mid;
+        }
+    }
+
+    lo
+}
+
+fn sort_entries(entries: &mut [(&str, usize, usize)]) -> Vec<u32> {
+    entries.sort_by(|a, b| a.0.cmp(b.0));
+
+    let mut reversed: Vec<u32> = (0..entries.len() as u32).collect();
+    reversed.sort_by(|a, b| cmp_reversed(entries[*a as usize].0, entries[*b as usize].0));
+    reversed
+}
+
+pub struct MemoryTitleTable {
+    titles: Vec<String>,
+    ranges: Vec<(usize, usize)>,
+    reversed: Vec<u32>,
+}
+
+impl MemoryTitleTable {
+    pub fn from(mut entries: Vec<(&str, usize, usize)>) -> Self {
+        let reversed = sort_entries(&mut entries);
+
+        MemoryTitleTable {
+            titles: entries.iter().map(|x| x.0.to_owned()).collect(),
+            ranges: entries.iter().map(|x| (x.1, x.2)).collect(),
+            reversed,
+        }
+    }
+}
+
+impl TitleTable for MemoryTitleTable {
+    fn len(&self) -> usize {
+        self.titles.len()
+    }
+
+    fn title(&self, i: usize) -> &str {
+        &self.titles[i]
+    }
+
+    fn range(&self, i: usize) -> (usize, usize) {
+        self.ranges[i]
+    }
+
+    fn reversed(&self, k: usize) -> usize {
+        self.reversed[k] as usize
+    }
+}
+
+// Binary title index layout (little-endian)
+//
+//   magic     8 bytes "HKRTITLE"
+//   version   u32
+//   reserved  u32
+//   count     u64
+//   entries   count * 32 bytes, sorted by title
+//             (title offset u64, title length u32, reserved u32, article start u64, article end u64)
+//   reversed  count * u32, entry indices sorted by reversed title
+//   titles    utf-8 title pool, title offsets are relative to here
+pub const TITLE_TABLE_MAGIC: &[u8] = b"HKRTITLE";
+
+// 3: titles of namespaced articles carry their namespace prefix.
+const TITLE_TABLE_VERSION: u32 = 3;
+const HEADER_SIZE: usize = 24;
+const ENTRY_SIZE: usize = 32;
+
+pub struct MappedTitleTable {
+    mmap: Mmap,
+    count: usize,
+    reversed: usize,
+    pool: usize,
+}
+
+impl MappedTitleTable {
+    pub fn write(
+        path: &str,
+        entries: &mut [(&str, usize, usize)],
+    ) -> Result<usize, Box<dyn Error>> {
+        let reversed = sort_entries(entries);
+
+        let mut writer = BufWriter::new(fs::File::create(path)?);
+
+        writer.write_all(TITLE_TABLE_MAGIC)?;
+        write_u32(&mut writer, TITLE_TABLE_VERSION)?;
+        write_u32(&mut writer, 0)?;
+        write_u64(&mut writer, entries.len() as u64)?;
+
+        let mut title_offset = 0;
+
+        for (title, start, end) in entries.iter() {
+            write_u64(&mut writer, title_offset as u64)?;
+            write_u32(&mut writer, title.len() as u32)?;
+            write_u32(&mut writer, 0)?;
+            write_u64(&mut writer, *start as u64)?;
+            write_u64(&mut writer, *end as u64)?;
+
+            title_offset += title.len();
+        }
+
+        for i in reversed {
+            write_u32(&mut writer, i)?;
+        }
+
+        for (title, _, _) in entries.iter() {
+            writer.write_all(title.as_bytes())?;
+        }
+
+        writer.flush()?;
+
+        Ok(entries.len())
+    }
+
+    pub fn open(path: &str) -> Result<Self, Box<dyn Error>> {
+        let mmap = map_file(path)?;
+
+        if mmap.len() < HEADER_SIZE || &mmap[..8] != TITLE_TABLE_MAGIC {
+            return Err(format!("'{}' is not a binary title index", path).into());
+        }
+
+        let version = read_u32(&mmap, 8);
+
+        if version != TITLE_TABLE_VERSION {
+            return Err(format!(
+                "Unsupported binary title index version {} (expected {}), build and convert it again",
+                version, TITLE_TABLE_VERSION
+            )
+            .into());
+        }
+
+        let count = read_u64(&mmap, 16) as usize;
+        let reversed = HEADER_SIZE + count * ENTRY_SIZE;
+        let pool = reversed + count * 4;
+
+        if mmap.len() < pool {
+            return Err(format!("Binary title index '{}' is truncated", path).into());
+        }
+
+        let table = MappedTitleTable {
+            mmap,
+            count,
+            reversed,
+            pool,
+        };
+
+        for i in 0..count {
+            let (offset, len) = table.title_span(i);
+
+            if offset + len > table.mmap.len()
+                || str::from_utf8(&table.mmap[offset..offset + len]).is_err()
+                || table.reversed(i) >= count
+            {
+                return Err(
+                    format!("Binary title index '{}' has a broken entry at {}", path, i).into(),
+                );
+            }
+        }
+
+        Ok(table)
+    }
+
+    fn title_span(&self, i: usize) -> (usize, usize) {
+        let entry = HEADER_SIZE + i * ENTRY_SIZE;
+
+        (
+            self.pool + read_u64(&self.mmap, entry) as usize,
+            read_u32(&self.mmap, entry + 8) as usize,
+        )
+    }
+}
+
+impl TitleTable for MappedTitleTable {
+    fn len(&self) -> usize {
+        self.count
+    }
+
+    fn title(&self, i: usize) -> &str {
+        let (offset, len) = self.title_span(i);
+
+        // validated in `open`
+        str::from_utf8(&self.mmap[offset..offset + len]).unwrap()
+    }
+
+    fn range(&self, i: usize) -> (usize, usize) {
+        let entry = HEADER_SIZE + i * ENTRY_SIZE;
+
+        (
+            read_u64(&self.mmap, entry + 16) as usize,
+            read_u64(&self.mmap, entry + 24) as usize,
+        )
+    }
+
+    fn reversed(&self, k: usize) -> usize {
+        read_u32(&self.mmap, self.reversed + k * 4) as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::loader::fixture::temp_path;
+
+    use super::{MappedTitleTable, MemoryTitleTable, TitleTable};
+
+    fn entries() -> Vec<(&'static str, usize, usize)> {
+        vec![
+            ("하쿠레이 신사", 10, 20),
+            ("博麗神社", 30, 40),
+            ("동방", 0, 9),
+            ("동방프로젝트", 50, 60),
+            ("동방 신사", 70, 80),
+            ("서든어택", 90, 100),
+        ]
+    }
+
+    fn check_table(table: &dyn TitleTable) {
+        assert_eq!(table.len(), 6);
+        assert_eq!(table.title(0), "博麗神社");
+        assert_eq!(
+            table.find("하쿠레이 신사").map(|i| table.range(i)),
+            Some((10, 20))
+        );
+        assert_eq!(
+            table.find("博麗神社").map(|i| table.range(i)),
+            Some((30, 40))
+        );
+        assert_eq!(table.find("없는 문서"), None);
+
+        let titles = |ids: Vec<usize>| ids.into_iter().map(|i| table.title(i)).collect::<Vec<_>>();
+
+        assert_eq!(
+            titles(table.find_prefix("동방").collect()),
+            vec!["동방", "동방 신사", "동방프로젝트"]
+        );
+        assert_eq!(
+            titles(table.find_prefix("없는").collect()),
+            Vec::<&str>::new()
+        );
+        assert_eq!(
+            titles(table.find_suffix("신사")),
+            vec!["동방 신사", "하쿠레이 신사"]
+        );
+        assert_eq!(titles(table.find_suffix("어택")), vec!["서든어택"]);
+        assert_eq!(titles(table.find_suffix("없는")), Vec::<&str>::new());
+    }
+
+    #[test]
+    fn memory_title_table_test() {
+        check_table(&MemoryTitleTable::from(entries()));
+    }
+
+    #[test]
+    fn mapped_title_table_test() {
+        let path = temp_path("title-table.bin");
+
+        MappedTitleTable::write(&path, &mut entries()).unwrap();
+
+        check_table(&MappedTitleTable::open(&path).unwrap());
+
+        // tables of another version are rejected
+        let mut raw = std::fs::read(&path).unwrap();
+        raw[8..12].copy_from_slice(&2u32.to_le_bytes());
+        std::fs::write(&path, raw).unwrap();
+
+        assert!(MappedTitleTable::open(&path).is_err());
+    }
+}
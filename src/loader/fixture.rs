@@ -0,0 +1,42 @@
+use std::{env, fs};
+
+use serde_json::json;
+
+// Write a small dump in the same layout as `namuwiki_20210301.json` into the
+// temp directory and return its path.
+pub fn write_dump(name: &str, articles: &[(&str, &str)]) -> String {
+    let articles: Vec<_> = articles.iter().map(|(x, y)| (0, *x, *y)).collect();
+
+    write_namespaced_dump(name, &articles)
+}
+
+// Same as `write_dump` with the namespace number of every article.
+pub fn write_namespaced_dump(name: &str, articles: &[(i64, &str, &str)]) -> String {
+    let entries: Vec<_> = articles
+        .iter()
+        .map(|(namespace, title, text)| {
+            json!({
+                "namespace": namespace,
+                "title": title,
+                "text": text,
+                "contributors": [],
+            })
+        })
+        .collect();
+
+    temp_file(name, &serde_json::to_string(&entries).unwrap())
+}
+
+pub fn temp_path(name: &str) -> String {
+    env::temp_dir()
+        .join(format!("hakurei-{}-{}", std::process::id(), name))
+        .to_str()
+        .unwrap()
+        .to_owned()
+}
+
+fn temp_file(name: &str, content: &str) -> String {
+    let path = temp_path(name);
+    fs::write(&path, content).unwrap();
+    path
+}
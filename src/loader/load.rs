@@ -1,11 +1,269 @@
-use std::{error::Error, fs};
+use std::{
+    error::Error,
+    fs,
+    io::{BufRead, BufReader, Read, Seek, SeekFrom},
+};
 
 use crate::model::article::Article;
 
 pub fn load_dump(path: &str) -> Result<Vec<Article>, Box<dyn Error>> {
-    let raw = fs::read_to_string(path)?;
+    DumpReader::open(path)?
+        .map(|entry| entry.map(|e| e.article))
+        .collect()
+}
+
+// Read the article at the byte range of a `DumpEntry`.
+pub fn read_entry(file: &fs::File, start: usize, end: usize) -> Result<Article, Box<dyn Error>> {
+    let mut file = file.try_clone()?;
+    file.seek(SeekFrom::Start(start as u64))?;
+
+    let mut buf = vec![0; end - start + 1];
+    file.read_exact(&mut buf)?;
+
+    Ok(serde_json::from_slice::<Article>(&buf)?.qualify_title())
+}
+
+// One article of the dump and the byte range of its json object.
+// `start` points to the opening '{' and `end` to the closing '}' (inclusive),
+// the same convention `TitleIndex` uses for its offsets.
+#[derive(Debug)]
+pub struct DumpEntry {
+    pub start: usize,
+    pub end: usize,
+    pub article: Article,
+}
+
+// Streaming reader over the top-level json array of the dump.
+// Only one article is kept in memory at a time.
+pub struct DumpReader<R: Read> {
+    reader: BufReader<R>,
+    offset: usize,
+    started: bool,
+    finished: bool,
+    buf: Vec<u8>,
+}
+
+impl DumpReader<fs::File> {
+    pub fn open(path: &str) -> Result<Self, Box<dyn Error>> {
+        Ok(Self::new(fs::File::open(path)?))
+    }
+}
+
+impl<R: Read> DumpReader<R> {
+    pub fn new(reader: R) -> Self {
+        DumpReader {
+            reader: BufReader::with_capacity(1 << 20, reader),
+            offset: 0,
+            started: false,
+            finished: false,
+            buf: Vec::new(),
+        }
+    }
+
+    fn next_entry(&mut self) -> Result<Option<DumpEntry>, Box<dyn Error>> {
+        let (start, end) = match self.next_object()? {
+            Some(range) => range,
+            None => return Ok(None),
+        };
+
+        let article: Article = serde_json::from_slice(&self.buf)
+            .map_err(|err| format!("Cannot parse article at offset {}: {}", start, err))?;
+
+        Ok(Some(DumpEntry {
+            start,
+            end,
+            article: article.qualify_title(),
+        }))
+    }
+
+    // Copy the next json object of the array into `self.buf` and return its range.
+    fn next_object(&mut self) -> Result<Option<(usize, usize)>, Box<dyn Error>> {
+        if !self.started {
+            match self.skip_whitespace()? {
+                Some(b'[') => self.consume(1),
+                Some(ch) => {
+                    return Err(self.unexpected(ch));
+                }
+                None => return Err("Dump is empty".into()),
+            }
+
+            self.started = true;
+        }
+
+        loop {
+            match self.skip_whitespace()? {
+                Some(b',') => self.consume(1),
+                Some(b']') => {
+                    self.consume(1);
+                    return Ok(None);
+                }
+                Some(b'{') => break,
+                Some(ch) => return Err(self.unexpected(ch)),
+                None => return Err("Unexpected end of dump, expect ']'".into()),
+            }
+        }
+
+        let start = self.offset;
+        let mut depth = 0;
+        let mut in_string = false;
+        let mut escape = false;
+
+        self.buf.clear();
+
+        loop {
+            let chunk = self.reader.fill_buf()?;
+
+            if chunk.is_empty() {
+                return Err(
+                    format!("Unexpected end of dump in article at offset {}", start).into(),
+                );
+            }
+
+            let mut closed_at = None;
+
+            for (i, &ch) in chunk.iter().enumerate() {
+                if in_string {
+                    if escape {
+                        escape = false;
+                    } else if ch == b'\\' {
+                        escape = true;
+                    } else if ch == b'"' {
+                        in_string = false;
+                    }
+                    continue;
+                }
+
+                match ch {
+                    b'"' => in_string = true,
+                    b'{' | b'[' => depth += 1,
+                    b'}' | b']' => {
+                        depth -= 1;
+
+                        if depth == 0 {
+                            closed_at = Some(i);
+                            break;
+                        }
+                    }
+                    _ => {}
+                }
+            }
+
+            match closed_at {
+                Some(i) => {
+                    self.buf.extend_from_slice(&chunk[..=i]);
+                    self.consume(i + 1);
+                    return Ok(Some((start, self.offset - 1)));
+                }
+                None => {
+                    let len = chunk.len();
+                    self.buf.extend_from_slice(chunk);
+                    self.consume(len);
+                }
+            }
+        }
+    }
+
+    fn skip_whitespace(&mut self) -> Result<Option<u8>, Box<dyn Error>> {
+        loop {
+            let chunk = self.reader.fill_buf()?;
+
+            if chunk.is_empty() {
+                return Ok(None);
+            }
+
+            match chunk.iter().position(|ch| !ch.is_ascii_whitespace()) {
+                Some(i) => {
+                    let ch = chunk[i];
+                    self.consume(i);
+                    return Ok(Some(ch));
+                }
+                None => {
+                    let len = chunk.len();
+                    self.consume(len);
+                }
+            }
+        }
+    }
+
+    fn consume(&mut self, amount: usize) {
+        self.reader.consume(amount);
+        self.offset += amount;
+    }
+
+    fn unexpected(&self, ch: u8) -> Box<dyn Error> {
+        format!(
+            "Unexpected character '{}' at offset {} in dump",
+            ch as char, self.offset
+        )
+        .into()
+    }
+}
+
+impl<R: Read> Iterator for DumpReader<R> {
+    type Item = Result<DumpEntry, Box<dyn Error>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+
+        match self.next_entry() {
+            Ok(Some(entry)) => Some(Ok(entry)),
+            Ok(None) => {
+                self.finished = true;
+                None
+            }
+            Err(err) => {
+                self.finished = true;
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use crate::model::namespace::Namespace;
+
+    use super::DumpReader;
+
+    const DUMP: &str = r##"[
+        {"namespace":0,"title":"하쿠레이 신사","text":"[[분류:장소]] \"}{\\","contributors":["a"]},
+        {"text":"#redirect 하쿠레이 신사","title":"博麗神社","namespace":0,"contributors":[]},
+        {"namespace":"2","title":"장소","text":"","contributors":[]}
+    ]"##;
+
+    #[test]
+    fn dump_reader_test() {
+        let entries = DumpReader::new(Cursor::new(DUMP))
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].article.title, "하쿠레이 신사");
+        assert_eq!(entries[0].article.text, "[[분류:장소]] \"}{\\");
+        assert_eq!(entries[1].article.title, "博麗神社");
+        assert_eq!(entries[0].article.namespace, Namespace::Document);
+        assert_eq!(entries[0].article.contributors, vec!["a"]);
+        assert_eq!(entries[2].article.namespace, Namespace::Category);
+        assert_eq!(entries[2].article.title, "분류:장소");
+        assert_eq!(entries[2].article.name(), "장소");
+
+        for entry in entries {
+            let raw = &DUMP.as_bytes()[entry.start..=entry.end];
+            assert_eq!(raw[0], b'{');
+            assert_eq!(raw[raw.len() - 1], b'}');
+        }
+    }
+
+    #[test]
+    fn dump_reader_error_test() {
+        let mut reader = DumpReader::new(Cursor::new("[{\"title\":\"a\",\"text\":\"b\"}"));
 
-    //
-    // https://stackoverflow.com/questions/57423880/handling-serde-error-and-other-error-type
-    serde_json::from_str(&raw).map_err(|err| Box::new(err) as Box<dyn std::error::Error>)
+        assert!(reader.next().unwrap().is_ok());
+        assert!(reader.next().unwrap().is_err());
+        assert!(reader.next().is_none());
+    }
 }
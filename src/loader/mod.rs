@@ -1 +1,3 @@
+#[cfg(test)]
+pub mod fixture;
 pub mod load;
@@ -3,20 +3,29 @@ pub mod index;
 pub mod loader;
 pub mod model;
 
-use std::{env, process::exit};
+use std::{env, path::Path, process::exit};
 
 use crate::{
     core::{
-        ir::IRBuilder,
+        ir::{IRBuilder, Instruction, InstructionType},
         printer::Printer,
         vm::{RuntimeRef, VirtualMachine},
     },
-    index::{category::CategoryIndex, title::TitleIndex},
+    index::{
+        body::BodyIndex, category::CategoryIndex, category_tree::CategoryTree, link::LinkIndex,
+        rank::RankIndex, redirect::RedirectTable, title::TitleIndex,
+    },
 };
 
 const DEFAULT_DUMP_PATH: &str = "namuwiki_20210301.json";
 const DEFAULT_TITLE_INDEX_PATH: &str = "title-index.json";
+const DEFAULT_TITLE_BINARY_INDEX_PATH: &str = "title-index.bin";
+const DEFAULT_BODY_INDEX_PATH: &str = "body-index.bin";
+const DEFAULT_LINK_INDEX_PATH: &str = "link-index.bin";
+const DEFAULT_RANK_INDEX_PATH: &str = "rank-index.bin";
+const DEFAULT_REDIRECT_TABLE_PATH: &str = "redirect-table.bin";
 const DEFAULT_CATEGORY_INDEX_PATH: &str = "article-with-categories.json";
+const DEFAULT_CATEGORY_TREE_PATH: &str = "category-tree.json";
 
 fn main() {
     let args: Vec<_> = env::args().collect();
@@ -26,6 +35,11 @@ fn main() {
         exit(0);
     }
 
+    if args[1] == "index" {
+        build_index(&args[2..]);
+        exit(0);
+    }
+
     let irb = IRBuilder::from(&args[1][..]).unwrap();
 
     let head_inst = irb.build();
@@ -33,12 +47,60 @@ fn main() {
 
     let vm = vm_from!(insts);
 
-    let tindex = TitleIndex::load(DEFAULT_DUMP_PATH, DEFAULT_TITLE_INDEX_PATH).unwrap();
+    let mut tindex = TitleIndex::load(DEFAULT_DUMP_PATH, default_title_index_path()).unwrap();
+
+    // redirects are followed document by document without the table, a stale one is left out
+    if Path::new(DEFAULT_REDIRECT_TABLE_PATH).exists() {
+        match RedirectTable::open(DEFAULT_REDIRECT_TABLE_PATH, &tindex) {
+            Ok(table) => tindex.set_redirect_table(table),
+            Err(e) => eprintln!(
+                "warning: {} is not used, build it again with `hakurei index redirect`: {}",
+                DEFAULT_REDIRECT_TABLE_PATH, e
+            ),
+        }
+    }
+
     let cindex = CategoryIndex::load(DEFAULT_CATEGORY_INDEX_PATH).unwrap();
 
+    // body queries stream the dump without the body index
+    let bindex = if calls(&head_inst, &["body:contains", "body:search"])
+        && Path::new(DEFAULT_BODY_INDEX_PATH).exists()
+    {
+        Some(BodyIndex::open(DEFAULT_DUMP_PATH, DEFAULT_BODY_INDEX_PATH).unwrap())
+    } else {
+        None
+    };
+
+    // a stale link index only fails the queries following links
+    let lindex = if calls(&head_inst, &["links_to", "path", "outlinks", "backlinks"])
+        && Path::new(DEFAULT_LINK_INDEX_PATH).exists()
+    {
+        Some(LinkIndex::open(DEFAULT_LINK_INDEX_PATH, &tindex).unwrap())
+    } else {
+        None
+    };
+
+    let rindex = if calls(&head_inst, &["rank"]) && Path::new(DEFAULT_RANK_INDEX_PATH).exists() {
+        Some(RankIndex::open(DEFAULT_RANK_INDEX_PATH, &tindex).unwrap())
+    } else {
+        None
+    };
+
+    let ctree = if calls(&head_inst, &["category:descendants"])
+        && Path::new(DEFAULT_CATEGORY_TREE_PATH).exists()
+    {
+        Some(CategoryTree::load(DEFAULT_CATEGORY_TREE_PATH).unwrap())
+    } else {
+        None
+    };
+
     let rt_ref = RuntimeRef {
         category_index: &cindex,
+        category_tree: ctree.as_ref(),
         title_index: &tindex,
+        body_index: bindex.as_ref(),
+        link_index: lindex.as_ref(),
+        rank_index: rindex.as_ref(),
         articles: None,
     };
 
@@ -48,3 +110,143 @@ fn main() {
 
     println!("{}", printer.do_print(result));
 }
+
+// Whether the query calls one of `names`, in lambdas and `bind` too.
+fn calls(inst: &Instruction, names: &[&str]) -> bool {
+    let called = match inst.inst_type {
+        InstructionType::FunctionCall | InstructionType::UseFunction => {
+            names.contains(&inst.data.as_deref().unwrap_or(""))
+        }
+        _ => false,
+    };
+
+    called || inst.params.iter().flatten().any(|x| calls(x, names))
+}
+
+// prefer the binary index, it does not need to be deserialized
+fn default_title_index_path() -> &'static str {
+    if Path::new(DEFAULT_TITLE_BINARY_INDEX_PATH).exists() {
+        DEFAULT_TITLE_BINARY_INDEX_PATH
+    } else {
+        DEFAULT_TITLE_INDEX_PATH
+    }
+}
+
+// hakurei index <title | category | category-tree | body | link | redirect> [dump path] [index path]
+// hakurei index title-binary [title-index.json path] [binary index path]
+// hakurei index title-ngram [title index path] [n-gram index path]
+// hakurei index title-fuzzy [title index path] [BK-tree path]
+// hakurei index title-hangul [title index path] [hangul index path]
+// hakurei index rank [dump path] [link index path] [index path]
+fn build_index(args: &[String]) {
+    let dump_path = args.get(1).map_or(DEFAULT_DUMP_PATH, |x| &x[..]);
+
+    match args.first().map(|x| &x[..]) {
+        Some("title-binary") => {
+            let json_path = args.get(1).map_or(DEFAULT_TITLE_INDEX_PATH, |x| &x[..]);
+            let binary_path = args
+                .get(2)
+                .map_or(DEFAULT_TITLE_BINARY_INDEX_PATH, |x| &x[..]);
+
+            let count = TitleIndex::convert(json_path, binary_path).unwrap();
+            println!("{} titles are converted to {}", count, binary_path);
+        }
+        Some("title-ngram") => {
+            let title_path = args.get(1).map_or(default_title_index_path(), |x| &x[..]);
+            let ngram_path = args
+                .get(2)
+                .cloned()
+                .unwrap_or_else(|| TitleIndex::companion_path(title_path, "ngram"));
+
+            let count = TitleIndex::build_ngram(title_path, &ngram_path).unwrap();
+            println!("{} titles are indexed to {}", count, ngram_path);
+        }
+        Some("title-fuzzy") => {
+            let title_path = args.get(1).map_or(default_title_index_path(), |x| &x[..]);
+            let fuzzy_path = args
+                .get(2)
+                .cloned()
+                .unwrap_or_else(|| TitleIndex::companion_path(title_path, "fuzzy"));
+
+            let count = TitleIndex::build_fuzzy(title_path, &fuzzy_path).unwrap();
+            println!("{} titles are indexed to {}", count, fuzzy_path);
+        }
+        Some("title-hangul") => {
+            let title_path = args.get(1).map_or(default_title_index_path(), |x| &x[..]);
+            let hangul_path = args
+                .get(2)
+                .cloned()
+                .unwrap_or_else(|| TitleIndex::companion_path(title_path, "hangul"));
+
+            let count = TitleIndex::build_hangul(title_path, &hangul_path).unwrap();
+            println!("{} titles are indexed to {}", count, hangul_path);
+        }
+        Some("title") => {
+            let index_path = args.get(2).map_or(DEFAULT_TITLE_INDEX_PATH, |x| &x[..]);
+
+            let count = TitleIndex::build(dump_path, index_path).unwrap();
+            println!("{} titles are indexed to {}", count, index_path);
+
+            let tindex = TitleIndex::load(dump_path, index_path).unwrap();
+            tindex.verify().unwrap();
+            println!("{} titles are verified", count);
+        }
+        Some("category") => {
+            let index_path = args.get(2).map_or(DEFAULT_CATEGORY_INDEX_PATH, |x| &x[..]);
+
+            let count = CategoryIndex::build(dump_path, index_path).unwrap();
+            println!("{} articles are indexed to {}", count, index_path);
+            println!(
+                "Categories are inverted to {}",
+                CategoryIndex::members_path(index_path)
+            );
+        }
+        Some("category-tree") => {
+            let index_path = args.get(2).map_or(DEFAULT_CATEGORY_TREE_PATH, |x| &x[..]);
+
+            let count = CategoryTree::build(dump_path, index_path).unwrap();
+            println!("{} categories are indexed to {}", count, index_path);
+        }
+        Some("body") => {
+            let index_path = args.get(2).map_or(DEFAULT_BODY_INDEX_PATH, |x| &x[..]);
+
+            let count = BodyIndex::build(dump_path, index_path).unwrap();
+            println!("{} articles are indexed to {}", count, index_path);
+        }
+        Some("link") => {
+            let index_path = args.get(2).map_or(DEFAULT_LINK_INDEX_PATH, |x| &x[..]);
+
+            // node ids are the ids of the title index the query mode loads
+            let tindex = TitleIndex::load(dump_path, default_title_index_path()).unwrap();
+
+            let count = LinkIndex::build(&tindex, dump_path, index_path).unwrap();
+            println!("{} links are indexed to {}", count, index_path);
+        }
+        Some("redirect") => {
+            let index_path = args.get(2).map_or(DEFAULT_REDIRECT_TABLE_PATH, |x| &x[..]);
+
+            let tindex = TitleIndex::load(dump_path, default_title_index_path()).unwrap();
+
+            let count = RedirectTable::build(&tindex, dump_path, index_path).unwrap();
+            println!("{} redirects are resolved to {}", count, index_path);
+        }
+        Some("rank") => {
+            let link_path = args.get(2).map_or(DEFAULT_LINK_INDEX_PATH, |x| &x[..]);
+            let index_path = args.get(3).map_or(DEFAULT_RANK_INDEX_PATH, |x| &x[..]);
+
+            let tindex = TitleIndex::load(dump_path, default_title_index_path()).unwrap();
+            let lindex = LinkIndex::open(link_path, &tindex).unwrap();
+
+            let count = RankIndex::build(&lindex, index_path).unwrap();
+            println!("{} articles are ranked to {}", count, index_path);
+        }
+        _ => println!(
+            "usage: hakurei index <title | category | category-tree | body | link | redirect> [dump path] [index path]\n       \
+             hakurei index title-binary [json index path] [binary index path]\n       \
+             hakurei index title-ngram [title index path] [n-gram index path]\n       \
+             hakurei index title-fuzzy [title index path] [BK-tree path]\n       \
+             hakurei index title-hangul [title index path] [hangul index path]\n       \
+             hakurei index rank [dump path] [link index path] [index path]"
+        ),
+    }
+}
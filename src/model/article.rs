@@ -1,38 +1,143 @@
-use regex::Regex;
 use serde::{Deserialize, Serialize};
 
-use super::article_category::ArticleCategory;
+use super::{
+    article_category::ArticleCategory, namespace::Namespace, namumark, namumark::Document,
+};
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+// Titles of articles read from the dump carry their namespace prefix, see `Namespace`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub struct Article {
     pub title: String,
     pub text: String,
+    #[serde(default)]
+    pub namespace: Namespace,
+    #[serde(default)]
+    pub contributors: Vec<String>,
 }
 
 impl Article {
-    pub fn categories(&self) -> Vec<&str> {
-        let mut result = Vec::new();
-        let re = Regex::new(r"\[\[분류:(.*?)\]\]").unwrap();
+    // Prefix the title with the namespace, done once when the article is read from the dump.
+    pub fn qualify_title(mut self) -> Self {
+        self.title = self.namespace.qualify(&self.title);
+        self
+    }
 
-        for cap in re.captures_iter(&self.text) {
-            result.push(cap.get(1).map_or("", |m| m.as_str()));
+    // Title without the namespace prefix.
+    pub fn name(&self) -> &str {
+        match self.namespace.prefix() {
+            Some(prefix) => self
+                .title
+                .strip_prefix(prefix)
+                .and_then(|x| x.strip_prefix(':'))
+                .unwrap_or(&self.title),
+            None => &self.title,
         }
+    }
+
+    pub fn categories(&self) -> Vec<&str> {
+        self.document().categories()
+    }
+
+    // Heading titles, e.g. `== 개요 ==` and folded `==# 개요 #==`, see `Document::headings`.
+    pub fn menus(&self) -> Vec<String> {
+        self.document().headings()
+    }
 
-        result
+    pub fn document(&self) -> Document<'_> {
+        Document::parse(&self.text)
     }
 
+    // Categories are taken from the parsed document (`#blur` is dropped), once each.
     pub fn to_article_category(&self) -> ArticleCategory {
+        let mut categories: Vec<&str> = Vec::new();
+
+        for category in self.categories() {
+            if !categories.contains(&category) {
+                categories.push(category);
+            }
+        }
+
         ArticleCategory {
             title: &self.title,
-            categories: self.categories(),
+            categories,
+        }
+    }
+
+    // Name of the category a category page describes. Dumps without namespaces keep the prefix
+    // in the title.
+    pub fn category_page(&self) -> Option<&str> {
+        match self.namespace {
+            Namespace::Category => Some(self.name()),
+            Namespace::Document => self.title.strip_prefix("분류:"),
+            _ => None,
         }
     }
 
+    // Documents are the members of their categories, redirects, category pages and other
+    // namespaces are not.
+    pub fn is_category_member(&self) -> bool {
+        self.namespace == Namespace::Document
+            && self.category_page().is_none()
+            && !self.is_redirect()
+    }
+
     pub fn is_redirect(&self) -> bool {
-        self.text.starts_with("#redirect")
+        self.redirect_target().is_some()
     }
 
+    // Target of a redirect document without its anchor, empty for other documents.
     pub fn get_redirect(&self) -> &str {
-        &self.text[10..].trim()
+        self.redirect_target().unwrap_or("")
+    }
+
+    fn redirect_target(&self) -> Option<&str> {
+        namumark::redirect_target(&self.text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Article;
+
+    #[test]
+    fn article_menus_test() {
+        let article = Article {
+            title: "동방프로젝트".to_owned(),
+            text:
+                "[목차]\n== 개요 ==\n내용\n=== '''역사''' ===\n==# 작품 목록 #==\n= 틀림 ==\n a == b ==\n==붙임=="
+                    .to_owned(),
+            ..Default::default()
+        };
+
+        assert_eq!(article.menus(), vec!["개요", "역사", "작품 목록"]);
+    }
+
+    #[test]
+    fn article_redirect_test() {
+        let redirect = |text: &str| {
+            let article = Article {
+                title: "레이무".to_owned(),
+                text: text.to_owned(),
+                ..Default::default()
+            };
+
+            (article.is_redirect(), article.get_redirect().to_owned())
+        };
+
+        assert_eq!(
+            redirect("#redirect 하쿠레이 레이무"),
+            (true, "하쿠레이 레이무".to_owned())
+        );
+        assert_eq!(
+            redirect("#REDIRECT  하쿠레이 레이무#s-2\n[[분류:동방]]"),
+            (true, "하쿠레이 레이무".to_owned())
+        );
+        assert_eq!(
+            redirect("#넘겨주기 하쿠레이 레이무"),
+            (true, "하쿠레이 레이무".to_owned())
+        );
+        assert_eq!(redirect("#redirect"), (true, "".to_owned()));
+        assert_eq!(redirect("하쿠레이 레이무"), (false, "".to_owned()));
+        assert_eq!(redirect("#"), (false, "".to_owned()));
     }
 }
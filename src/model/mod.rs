@@ -1,2 +1,4 @@
 pub mod article;
 pub mod article_category;
+pub mod namespace;
+pub mod namumark;
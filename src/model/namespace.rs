@@ -0,0 +1,154 @@
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+// Namespace of a dump entry. The dump stores it as a number, titles do not carry the prefix
+// (`분류:동방 프로젝트` is the title `동방 프로젝트` in the category namespace). Loaded articles
+// are titled with the prefix, so every namespace is a sorted range of the title index.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Namespace {
+    #[default]
+    Document,
+    Template,
+    Category,
+    File,
+    User,
+    Wiki,
+    Other(i64),
+}
+
+impl Namespace {
+    pub fn from_number(number: i64) -> Self {
+        match number {
+            0 => Namespace::Document,
+            1 => Namespace::Template,
+            2 => Namespace::Category,
+            3 => Namespace::File,
+            4 => Namespace::User,
+            6 => Namespace::Wiki,
+            x => Namespace::Other(x),
+        }
+    }
+
+    pub fn number(&self) -> i64 {
+        match self {
+            Namespace::Document => 0,
+            Namespace::Template => 1,
+            Namespace::Category => 2,
+            Namespace::File => 3,
+            Namespace::User => 4,
+            Namespace::Wiki => 6,
+            Namespace::Other(x) => *x,
+        }
+    }
+
+    // Query names, `ns:template(...)`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "document" => Some(Namespace::Document),
+            "template" => Some(Namespace::Template),
+            "category" => Some(Namespace::Category),
+            "file" => Some(Namespace::File),
+            "user" => Some(Namespace::User),
+            "wiki" => Some(Namespace::Wiki),
+            _ => None,
+        }
+    }
+
+    pub fn from_prefix(prefix: &str) -> Option<Self> {
+        match prefix {
+            "문서" => Some(Namespace::Document),
+            "틀" => Some(Namespace::Template),
+            "분류" => Some(Namespace::Category),
+            "파일" => Some(Namespace::File),
+            "사용자" => Some(Namespace::User),
+            "나무위키" => Some(Namespace::Wiki),
+            _ => None,
+        }
+    }
+
+    // Title prefix used in links, `[[분류:동방 프로젝트]]`.
+    pub fn prefix(&self) -> Option<&'static str> {
+        match self {
+            Namespace::Document | Namespace::Other(_) => None,
+            Namespace::Template => Some("틀"),
+            Namespace::Category => Some("분류"),
+            Namespace::File => Some("파일"),
+            Namespace::User => Some("사용자"),
+            Namespace::Wiki => Some("나무위키"),
+        }
+    }
+
+    // `동방 프로젝트` => `분류:동방 프로젝트`, titles which already have the prefix are kept.
+    pub fn qualify(&self, title: &str) -> String {
+        match self.prefix() {
+            Some(prefix) if Self::split(title).0 != *self => format!("{}:{}", prefix, title),
+            _ => title.to_owned(),
+        }
+    }
+
+    // `분류:동방 프로젝트` => (Category, `동방 프로젝트`), titles without a known prefix are
+    // documents.
+    pub fn split(title: &str) -> (Self, &str) {
+        title
+            .split_once(':')
+            .and_then(|(prefix, name)| Some((Self::from_prefix(prefix)?, name)))
+            .filter(|(namespace, _)| *namespace != Namespace::Document)
+            .unwrap_or((Namespace::Document, title))
+    }
+}
+
+impl Serialize for Namespace {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_i64(self.number())
+    }
+}
+
+impl<'de> Deserialize<'de> for Namespace {
+    // Accept `2`, `"2"` and `"분류"`.
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Raw {
+            Number(i64),
+            Text(String),
+        }
+
+        match Raw::deserialize(deserializer)? {
+            Raw::Number(x) => Ok(Namespace::from_number(x)),
+            Raw::Text(x) => match x.parse() {
+                Ok(x) => Ok(Namespace::from_number(x)),
+                Err(_) => Namespace::from_prefix(&x)
+                    .ok_or_else(|| serde::de::Error::custom(format!("unknown namespace '{}'", x))),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Namespace;
+
+    #[test]
+    fn namespace_title_test() {
+        assert_eq!(
+            Namespace::Category.qualify("동방 프로젝트"),
+            "분류:동방 프로젝트"
+        );
+        assert_eq!(
+            Namespace::Template.qualify("틀:동방 프로젝트"),
+            "틀:동방 프로젝트"
+        );
+        assert_eq!(Namespace::Document.qualify("Re:제로"), "Re:제로");
+        assert_eq!(
+            Namespace::split("분류:동방 프로젝트"),
+            (Namespace::Category, "동방 프로젝트")
+        );
+        assert_eq!(
+            Namespace::split("Re:제로"),
+            (Namespace::Document, "Re:제로")
+        );
+        assert_eq!(
+            Namespace::split("문서:제로"),
+            (Namespace::Document, "문서:제로")
+        );
+    }
+}
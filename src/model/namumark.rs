@@ -0,0 +1,1188 @@
+use std::sync::OnceLock;
+
+use regex::Regex;
+
+// Namumark (namuwiki markup) parser.
+//
+// The tree borrows every piece of text from the article, nothing is unescaped or copied.
+// Link targets are kept as written, relative links such as `[[../]]` are not resolved.
+// Markup which cannot be closed is kept as plain text, like namuwiki renders it.
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Document<'a> {
+    pub blocks: Vec<Block<'a>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Block<'a> {
+    // `#redirect 문서`, only on the first line
+    Redirect(&'a str),
+    // `## comment`
+    Comment(&'a str),
+    // `== title ==`, folded headings are written `==# title #==`
+    Heading {
+        level: usize,
+        folded: bool,
+        title: Vec<Inline<'a>>,
+    },
+    Paragraph(Vec<Inline<'a>>),
+    // consecutive ` * item` and ` 1. item` lines
+    List(Vec<ListItem<'a>>),
+    // consecutive `> quote` lines
+    Quote(Vec<Inline<'a>>),
+    Table(Vec<Vec<TableCell<'a>>>),
+    // `----`
+    HorizontalRule,
+    // `{{{#!folding summary ... }}}`
+    Folding {
+        summary: &'a str,
+        blocks: Vec<Block<'a>>,
+    },
+    // `{{{#!wiki style="..." ... }}}`
+    Wiki {
+        attributes: &'a str,
+        blocks: Vec<Block<'a>>,
+    },
+    // `{{{#!syntax cpp ... }}}` or a `{{{ ... }}}` spanning lines
+    Code {
+        language: Option<&'a str>,
+        text: &'a str,
+    },
+    // `{{{#!html ... }}}`
+    Html(&'a str),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ListItem<'a> {
+    // number of leading spaces
+    pub depth: usize,
+    // `*`, `1.`, `a.`, `A.`, `i.` or `I.`
+    pub marker: &'a str,
+    pub content: Vec<Inline<'a>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TableCell<'a> {
+    // number of columns, `||||` spans two
+    pub span: usize,
+    // `<bgcolor=#fff>`, `<-2>`, ... without the angle brackets
+    pub attributes: Vec<&'a str>,
+    pub content: Vec<Inline<'a>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Style<'a> {
+    Bold,
+    Italic,
+    Underline,
+    Strike,
+    Superscript,
+    Subscript,
+    // `{{{#red text}}}`, `{{{#ff0000,#00ff00 text}}}`
+    Color(&'a str),
+    // `{{{+1 text}}}`
+    Size(i8),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Inline<'a> {
+    Text(&'a str),
+    LineBreak,
+    Styled {
+        style: Style<'a>,
+        children: Vec<Inline<'a>>,
+    },
+    // `{{{text}}}`, rendered as is
+    Literal(&'a str),
+    // `[[target#anchor|label]]`
+    Link {
+        target: &'a str,
+        anchor: Option<&'a str>,
+        label: Option<Vec<Inline<'a>>>,
+    },
+    // `[[분류:name]]`
+    Category(&'a str),
+    // `[[파일:name|options]]`
+    File {
+        name: &'a str,
+        options: Option<&'a str>,
+    },
+    // `[include(틀:name, key=value)]`
+    Include {
+        target: &'a str,
+        params: Vec<(&'a str, &'a str)>,
+    },
+    // `[* text]` or `[*name text]`
+    Footnote {
+        name: Option<&'a str>,
+        children: Vec<Inline<'a>>,
+    },
+    // `[목차]`, `[br]`, `[youtube(id)]`, ...
+    Macro {
+        name: &'a str,
+        args: Option<&'a str>,
+    },
+}
+
+const STYLES: [(&str, Style); 7] = [
+    ("'''", Style::Bold),
+    ("''", Style::Italic),
+    ("__", Style::Underline),
+    ("~~", Style::Strike),
+    ("--", Style::Strike),
+    ("^^", Style::Superscript),
+    (",,", Style::Subscript),
+];
+
+const LIST_MARKERS: [&str; 6] = ["*", "1.", "a.", "A.", "i.", "I."];
+
+impl<'a> Document<'a> {
+    pub fn parse(text: &'a str) -> Self {
+        Document {
+            blocks: parse_blocks(text, true),
+        }
+    }
+
+    // Call `f` on every inline of the document, including nested ones.
+    pub fn visit(&self, f: &mut impl FnMut(&Inline<'a>)) {
+        visit_blocks(&self.blocks, f);
+    }
+
+    // Link targets in document order, categories and files are not links.
+    pub fn links(&self) -> Vec<&'a str> {
+        let mut result = Vec::new();
+
+        self.visit(&mut |inline| {
+            if let Inline::Link { target, .. } = inline {
+                if !target.is_empty() {
+                    result.push(*target);
+                }
+            }
+        });
+
+        result
+    }
+
+    pub fn categories(&self) -> Vec<&'a str> {
+        let mut result = Vec::new();
+
+        self.visit(&mut |inline| {
+            if let Inline::Category(name) = inline {
+                result.push(*name);
+            }
+        });
+
+        result
+    }
+
+    pub fn includes(&self) -> Vec<&'a str> {
+        let mut result = Vec::new();
+
+        self.visit(&mut |inline| {
+            if let Inline::Include { target, .. } = inline {
+                result.push(*target);
+            }
+        });
+
+        result
+    }
+
+    // Heading titles as rendered, in document order, including those in foldings.
+    pub fn headings(&self) -> Vec<String> {
+        let mut result = Vec::new();
+
+        heading_blocks(&self.blocks, &mut result);
+
+        result
+    }
+
+    // The text a reader sees, one block per line and footnotes at the end.
+    // Categories, files, includes, html and comments are dropped.
+    pub fn to_plain_text(&self) -> String {
+        let mut out = String::new();
+        let mut footnotes = Vec::new();
+
+        render_blocks(&self.blocks, &mut out, &mut footnotes);
+
+        // footnotes may contain footnotes
+        let mut i = 0;
+
+        while i < footnotes.len() {
+            let children = footnotes[i];
+            render_line(children, &mut out, &mut footnotes);
+            i += 1;
+        }
+
+        out
+    }
+}
+
+fn heading_blocks(blocks: &[Block], result: &mut Vec<String>) {
+    for block in blocks {
+        match block {
+            Block::Heading { title, .. } => {
+                let mut out = String::new();
+
+                render_inlines(title, &mut out, &mut Vec::new());
+                result.push(out);
+            }
+            Block::Folding { blocks, .. } | Block::Wiki { blocks, .. } => {
+                heading_blocks(blocks, result)
+            }
+            _ => {}
+        }
+    }
+}
+
+fn visit_blocks<'a>(blocks: &[Block<'a>], f: &mut impl FnMut(&Inline<'a>)) {
+    for block in blocks {
+        match block {
+            Block::Heading { title: inlines, .. }
+            | Block::Paragraph(inlines)
+            | Block::Quote(inlines) => visit_inlines(inlines, f),
+            Block::List(items) => items.iter().for_each(|x| visit_inlines(&x.content, f)),
+            Block::Table(rows) => rows
+                .iter()
+                .flatten()
+                .for_each(|x| visit_inlines(&x.content, f)),
+            Block::Folding { blocks, .. } | Block::Wiki { blocks, .. } => visit_blocks(blocks, f),
+            _ => {}
+        }
+    }
+}
+
+fn visit_inlines<'a>(inlines: &[Inline<'a>], f: &mut impl FnMut(&Inline<'a>)) {
+    for inline in inlines {
+        f(inline);
+
+        match inline {
+            Inline::Styled { children, .. } | Inline::Footnote { children, .. } => {
+                visit_inlines(children, f)
+            }
+            Inline::Link {
+                label: Some(label), ..
+            } => visit_inlines(label, f),
+            _ => {}
+        }
+    }
+}
+
+fn render_blocks<'a, 'b>(
+    blocks: &'b [Block<'a>],
+    out: &mut String,
+    footnotes: &mut Vec<&'b [Inline<'a>]>,
+) {
+    for block in blocks {
+        match block {
+            Block::Heading { title: inlines, .. }
+            | Block::Paragraph(inlines)
+            | Block::Quote(inlines) => render_line(inlines, out, footnotes),
+            Block::List(items) => {
+                for item in items {
+                    render_line(&item.content, out, footnotes);
+                }
+            }
+            Block::Table(rows) => {
+                for row in rows {
+                    for (i, cell) in row.iter().enumerate() {
+                        if i != 0 {
+                            out.push('\t');
+                        }
+
+                        render_inlines(&cell.content, out, footnotes);
+                    }
+
+                    out.push('\n');
+                }
+            }
+            Block::Folding { summary, blocks } => {
+                out.push_str(summary);
+                out.push('\n');
+                render_blocks(blocks, out, footnotes);
+            }
+            Block::Wiki { blocks, .. } => render_blocks(blocks, out, footnotes),
+            Block::Code { text, .. } => {
+                out.push_str(text);
+                out.push('\n');
+            }
+            Block::Redirect(_) | Block::Comment(_) | Block::HorizontalRule | Block::Html(_) => {}
+        }
+    }
+}
+
+// Lines which render to nothing, e.g. a lone category, are dropped.
+fn render_line<'a, 'b>(
+    inlines: &'b [Inline<'a>],
+    out: &mut String,
+    footnotes: &mut Vec<&'b [Inline<'a>]>,
+) {
+    let len = out.len();
+
+    render_inlines(inlines, out, footnotes);
+
+    if out.len() != len {
+        out.push('\n');
+    }
+}
+
+fn render_inlines<'a, 'b>(
+    inlines: &'b [Inline<'a>],
+    out: &mut String,
+    footnotes: &mut Vec<&'b [Inline<'a>]>,
+) {
+    for inline in inlines {
+        match inline {
+            Inline::Text(text) | Inline::Literal(text) => out.push_str(text),
+            Inline::LineBreak => out.push('\n'),
+            Inline::Styled { children, .. } => render_inlines(children, out, footnotes),
+            Inline::Link {
+                label: Some(label), ..
+            } => render_inlines(label, out, footnotes),
+            Inline::Link { target, anchor, .. } => {
+                out.push_str(target);
+
+                if let Some(anchor) = anchor {
+                    out.push('#');
+                    out.push_str(anchor);
+                }
+            }
+            Inline::Footnote { children, .. } => footnotes.push(children),
+            Inline::Macro { name, .. } if name.eq_ignore_ascii_case("br") => out.push('\n'),
+            Inline::Category(_)
+            | Inline::File { .. }
+            | Inline::Include { .. }
+            | Inline::Macro { .. } => {}
+        }
+    }
+}
+
+fn parse_blocks(text: &str, document_start: bool) -> Vec<Block<'_>> {
+    let mut blocks: Vec<Block> = Vec::new();
+    // pending paragraph lines
+    let mut paragraph: Option<(usize, usize)> = None;
+    let mut pos = 0;
+
+    fn flush<'a>(
+        text: &'a str,
+        blocks: &mut Vec<Block<'a>>,
+        paragraph: &mut Option<(usize, usize)>,
+    ) {
+        if let Some((start, end)) = paragraph.take() {
+            blocks.push(Block::Paragraph(parse_inlines(&text[start..end])));
+        }
+    }
+
+    while pos < text.len() {
+        let line_end = text[pos..].find('\n').map_or(text.len(), |i| pos + i);
+        let line = &text[pos..line_end];
+
+        if let Some((block, end)) = parse_brace_block(text, pos) {
+            flush(text, &mut blocks, &mut paragraph);
+            blocks.push(block);
+            pos = end;
+            continue;
+        }
+
+        if line.starts_with("||") {
+            let end = table_end(text, pos);
+
+            flush(text, &mut blocks, &mut paragraph);
+            blocks.push(Block::Table(parse_table(&text[pos..end])));
+            pos = end + 1;
+            continue;
+        }
+
+        if let Some(block) = parse_line_block(line, document_start && pos == 0) {
+            flush(text, &mut blocks, &mut paragraph);
+
+            // consecutive list items and quote lines form one block
+            match (blocks.last_mut(), block) {
+                (Some(Block::List(items)), Block::List(mut item)) => items.append(&mut item),
+                (Some(Block::Quote(lines)), Block::Quote(mut line)) => {
+                    lines.push(Inline::LineBreak);
+                    lines.append(&mut line);
+                }
+                (_, block) => blocks.push(block),
+            }
+
+            pos = line_end + 1;
+            continue;
+        }
+
+        if line.trim().is_empty() {
+            flush(text, &mut blocks, &mut paragraph);
+        } else {
+            paragraph = Some((paragraph.map_or(pos, |x| x.0), line_end));
+        }
+
+        pos = line_end + 1;
+    }
+
+    flush(text, &mut blocks, &mut paragraph);
+
+    blocks
+}
+
+// `#redirect 문서`, `#넘겨주기 문서` in any case, the target ends at the first line break and
+// its anchor is dropped.
+pub fn redirect_target(text: &str) -> Option<&str> {
+    let rest = ["#redirect", "#넘겨주기"].iter().find_map(|keyword| {
+        let head = text.get(..keyword.len())?;
+
+        if head.eq_ignore_ascii_case(keyword) {
+            Some(&text[keyword.len()..])
+        } else {
+            None
+        }
+    })?;
+
+    let target = rest.lines().next().unwrap_or("").trim();
+
+    Some(match target.find('#') {
+        Some(i) => target[..i].trim_end(),
+        None => target,
+    })
+}
+
+// Blocks which take exactly one line.
+fn parse_line_block(line: &str, first_line: bool) -> Option<Block<'_>> {
+    static HEADING: OnceLock<Regex> = OnceLock::new();
+
+    if first_line {
+        if let Some(target) = redirect_target(line) {
+            return Some(Block::Redirect(target));
+        }
+    }
+
+    if let Some(comment) = line.strip_prefix("##") {
+        return Some(Block::Comment(comment));
+    }
+
+    let heading =
+        HEADING.get_or_init(|| Regex::new(r"^(={1,6})(#?) (.*?) (#?)(={1,6})\s*$").unwrap());
+
+    if let Some(cap) = heading.captures(line) {
+        let level = cap[1].len();
+
+        if level == cap[5].len() && cap[2] == cap[4] {
+            return Some(Block::Heading {
+                level,
+                folded: !cap[2].is_empty(),
+                title: parse_inlines(cap.get(3).unwrap().as_str()),
+            });
+        }
+    }
+
+    let trimmed = line.trim();
+
+    if (4..=9).contains(&trimmed.len()) && trimmed.chars().all(|c| c == '-') {
+        return Some(Block::HorizontalRule);
+    }
+
+    let content = line.trim_start_matches(' ');
+    let depth = line.len() - content.len();
+
+    if depth > 0 {
+        for marker in LIST_MARKERS {
+            if let Some(rest) = content.strip_prefix(marker) {
+                // ` 1.#3 item` starts the numbering at 3
+                let rest = match rest.strip_prefix('#') {
+                    Some(x) => x.trim_start_matches(|c: char| c.is_ascii_digit()),
+                    None => rest,
+                };
+
+                return Some(Block::List(vec![ListItem {
+                    depth,
+                    marker,
+                    content: parse_inlines(rest.trim_start()),
+                }]));
+            }
+        }
+    }
+
+    if let Some(quote) = line.strip_prefix('>') {
+        return Some(Block::Quote(parse_inlines(quote.trim_start())));
+    }
+
+    None
+}
+
+// `{{{` at the start of a line which opens a block, returns the block and the offset after it.
+fn parse_brace_block(text: &str, pos: usize) -> Option<(Block<'_>, usize)> {
+    let line = &text[pos..];
+    let open = pos + (line.len() - line.trim_start().len());
+
+    if !text[open..].starts_with("{{{") {
+        return None;
+    }
+
+    let close = open + 3 + find_closing(&text[open + 3..], "{{{", "}}}")?;
+    let content = &text[open + 3..close];
+
+    let (head, body) = match content.find('\n') {
+        Some(i) => (&content[..i], &content[i + 1..]),
+        None => (content, ""),
+    };
+
+    let block = if let Some(summary) = head.strip_prefix("#!folding") {
+        Block::Folding {
+            summary: summary.trim(),
+            blocks: parse_blocks(body, false),
+        }
+    } else if let Some(attributes) = head.strip_prefix("#!wiki") {
+        Block::Wiki {
+            attributes: attributes.trim(),
+            blocks: parse_blocks(body, false),
+        }
+    } else if let Some(language) = head.strip_prefix("#!syntax") {
+        Block::Code {
+            language: Some(language.trim()).filter(|x| !x.is_empty()),
+            text: body,
+        }
+    } else if let Some(html) = content.strip_prefix("#!html") {
+        Block::Html(html.trim())
+    } else if content.contains('\n') && style_prefix(content).is_none() {
+        Block::Code {
+            language: None,
+            text: content.strip_prefix('\n').unwrap_or(content),
+        }
+    } else {
+        // a literal or a styled text inside a paragraph
+        return None;
+    };
+
+    let mut end = close + 3;
+
+    if text[end..].starts_with('\n') {
+        end += 1;
+    }
+
+    Some((block, end))
+}
+
+// End of the table starting at `pos`, the offset of its last line end.
+// Rows end with `||` at the end of a line, a cell may span lines.
+fn table_end(text: &str, pos: usize) -> usize {
+    let mut end = pos;
+    let mut cursor = pos;
+    let mut row_start: Option<usize> = None;
+
+    while cursor < text.len() {
+        let line_end = text[cursor..].find('\n').map_or(text.len(), |i| cursor + i);
+
+        if row_start.is_none() && !text[cursor..line_end].starts_with("||") {
+            break;
+        }
+
+        let start = *row_start.get_or_insert(cursor);
+
+        if is_row(&text[start..line_end]) {
+            row_start = None;
+        }
+
+        end = line_end;
+        cursor = line_end + 1;
+    }
+
+    end
+}
+
+fn is_row(row: &str) -> bool {
+    let row = row.trim_end();
+    row.len() >= 4 && row.ends_with("||")
+}
+
+fn parse_table(text: &str) -> Vec<Vec<TableCell<'_>>> {
+    let mut rows = Vec::new();
+    let mut row_start: Option<usize> = None;
+    let mut pos = 0;
+
+    while pos < text.len() {
+        let line_end = text[pos..].find('\n').map_or(text.len(), |i| pos + i);
+        let start = *row_start.get_or_insert(pos);
+
+        if is_row(&text[start..line_end]) {
+            let row = text[start..line_end].trim_end();
+            rows.push(parse_row(&row[2..row.len() - 2]));
+            row_start = None;
+        }
+
+        pos = line_end + 1;
+    }
+
+    // a row which is never closed keeps its text
+    if let Some(start) = row_start {
+        rows.push(parse_row(&text[start + 2..]));
+    }
+
+    rows
+}
+
+fn parse_row(row: &str) -> Vec<TableCell<'_>> {
+    let mut cells = Vec::new();
+    let mut span = 1;
+
+    for cell in split_top(row, "||") {
+        // `||||` merges the next cell with this one
+        if cell.is_empty() {
+            span += 1;
+            continue;
+        }
+
+        let mut content = cell;
+        let mut attributes = Vec::new();
+
+        while let Some(rest) = content.strip_prefix('<') {
+            match rest.find('>') {
+                Some(i) if !rest[..i].contains(['<', '\n']) && i > 0 => {
+                    attributes.push(&rest[..i]);
+                    content = &rest[i + 1..];
+                }
+                _ => break,
+            }
+        }
+
+        cells.push(TableCell {
+            span,
+            attributes,
+            content: parse_inlines(content.trim()),
+        });
+        span = 1;
+    }
+
+    cells
+}
+
+pub fn parse_inlines(text: &str) -> Vec<Inline<'_>> {
+    let mut result = Vec::new();
+    let mut start = 0;
+    let mut i = 0;
+
+    while i < text.len() {
+        let rest = &text[i..];
+
+        let parsed = if let Some(escaped) = rest.strip_prefix('\\') {
+            escaped
+                .chars()
+                .next()
+                .map(|c| (Inline::Text(&escaped[..c.len_utf8()]), 1 + c.len_utf8()))
+        } else if rest.starts_with('\n') {
+            Some((Inline::LineBreak, 1))
+        } else if rest.starts_with("[[") {
+            parse_link(rest)
+        } else if rest.starts_with("[*") {
+            parse_footnote(rest)
+        } else if rest.starts_with('[') {
+            parse_macro(rest)
+        } else if rest.starts_with("{{{") {
+            parse_literal(rest)
+        } else {
+            parse_style(rest)
+        };
+
+        match parsed {
+            Some((inline, len)) => {
+                if start < i {
+                    result.push(Inline::Text(&text[start..i]));
+                }
+
+                result.push(inline);
+                i += len;
+                start = i;
+            }
+            None => i += rest.chars().next().unwrap().len_utf8(),
+        }
+    }
+
+    if start < text.len() {
+        result.push(Inline::Text(&text[start..]));
+    }
+
+    result
+}
+
+fn parse_style(text: &str) -> Option<(Inline<'_>, usize)> {
+    for (token, style) in STYLES {
+        if let Some(rest) = text.strip_prefix(token) {
+            // styles do not span lines
+            let line = &rest[..rest.find('\n').unwrap_or(rest.len())];
+
+            match line.find(token) {
+                Some(close) if close > 0 => {
+                    return Some((
+                        Inline::Styled {
+                            style,
+                            children: parse_inlines(&rest[..close]),
+                        },
+                        token.len() * 2 + close,
+                    ))
+                }
+                // `'''` may still open `''`
+                _ => continue,
+            }
+        }
+    }
+
+    None
+}
+
+fn parse_link(text: &str) -> Option<(Inline<'_>, usize)> {
+    let close = find_closing(&text[2..], "[[", "]]")?;
+    let inner = &text[2..2 + close];
+
+    let (target, label) = match split_top(inner, "|").as_slice() {
+        [target] => (*target, None),
+        [target, ..] => (*target, Some(&inner[target.len() + 1..])),
+        [] => return None,
+    };
+    let target = target.trim();
+
+    let inline = if let Some(name) = target.strip_prefix("분류:") {
+        // `#blur` hides the category from the list
+        Inline::Category(name.trim_end_matches("#blur").trim())
+    } else if let Some(name) = target.strip_prefix("파일:") {
+        Inline::File {
+            name: name.trim(),
+            options: label,
+        }
+    } else {
+        let (target, anchor) = match find_unescaped(target, '#') {
+            Some(i) => (target[..i].trim_end(), Some(&target[i + 1..])),
+            None => (target, None),
+        };
+
+        Inline::Link {
+            target,
+            anchor,
+            label: label.map(parse_inlines),
+        }
+    };
+
+    Some((inline, 2 + close + 2))
+}
+
+fn parse_footnote(text: &str) -> Option<(Inline<'_>, usize)> {
+    let close = find_closing(&text[1..], "[", "]")?;
+    let inner = &text[2..1 + close];
+
+    let (name, content) = match inner.find(' ') {
+        Some(0) => (None, &inner[1..]),
+        Some(i) => (Some(&inner[..i]), &inner[i + 1..]),
+        None if inner.is_empty() => (None, inner),
+        None => (Some(inner), ""),
+    };
+
+    Some((
+        Inline::Footnote {
+            name,
+            children: parse_inlines(content),
+        },
+        1 + close + 1,
+    ))
+}
+
+fn parse_macro(text: &str) -> Option<(Inline<'_>, usize)> {
+    let name_len = text[1..]
+        .find(|c: char| !(c.is_ascii_alphabetic() || ('가'..='힣').contains(&c)))
+        .unwrap_or(text.len() - 1);
+
+    if name_len == 0 {
+        return None;
+    }
+
+    let name = &text[1..1 + name_len];
+    let rest = &text[1 + name_len..];
+
+    let (args, len) = if rest.starts_with(']') {
+        (None, 1 + name_len + 1)
+    } else if let Some(rest) = rest.strip_prefix('(') {
+        let close = rest.find(")]")?;
+        (Some(&rest[..close]), 1 + name_len + 1 + close + 2)
+    } else {
+        return None;
+    };
+
+    let inline = match (name, args) {
+        ("include", Some(args)) => {
+            let mut parts = args.split(',');
+            let target = parts.next().unwrap_or("").trim();
+
+            Inline::Include {
+                target,
+                params: parts
+                    .filter_map(|x| x.split_once('='))
+                    .map(|(k, v)| (k.trim(), v.trim()))
+                    .collect(),
+            }
+        }
+        _ => Inline::Macro { name, args },
+    };
+
+    Some((inline, len))
+}
+
+fn parse_literal(text: &str) -> Option<(Inline<'_>, usize)> {
+    let close = find_closing(&text[3..], "{{{", "}}}")?;
+    let inner = &text[3..3 + close];
+
+    let inline = match style_prefix(inner) {
+        Some((style, len)) => Inline::Styled {
+            style,
+            children: parse_inlines(&inner[len..]),
+        },
+        None => Inline::Literal(inner),
+    };
+
+    Some((inline, 3 + close + 3))
+}
+
+// `#color ` or `+n ` at the start of `{{{`, returns the style and the length of the prefix.
+fn style_prefix(inner: &str) -> Option<(Style<'_>, usize)> {
+    let space = inner.find([' ', '\n'])?;
+    let head = &inner[..space];
+
+    if head.starts_with('#')
+        && !head.starts_with("#!")
+        && head
+            .split(',')
+            .all(|x| x.chars().count() > 1 && x.chars().skip(1).all(|c| c.is_ascii_alphanumeric()))
+    {
+        return Some((Style::Color(head), space + 1));
+    }
+
+    match head.as_bytes() {
+        [sign @ (b'+' | b'-'), level @ b'1'..=b'5'] => {
+            let level = (level - b'0') as i8;
+            let size = if *sign == b'+' { level } else { -level };
+
+            Some((Style::Size(size), space + 1))
+        }
+        _ => None,
+    }
+}
+
+// Offset of the `close` matching an `open` right before `text`, nested pairs are skipped.
+fn find_closing(text: &str, open: &str, close: &str) -> Option<usize> {
+    let mut depth = 0;
+    let mut i = 0;
+
+    while i < text.len() {
+        let rest = &text[i..];
+
+        if let Some(escaped) = rest.strip_prefix('\\') {
+            i += 1 + escaped.chars().next().map_or(0, |c| c.len_utf8());
+        } else if rest.starts_with(close) {
+            if depth == 0 {
+                return Some(i);
+            }
+
+            depth -= 1;
+            i += close.len();
+        } else if rest.starts_with(open) {
+            depth += 1;
+            i += open.len();
+        } else {
+            i += rest.chars().next().unwrap().len_utf8();
+        }
+    }
+
+    None
+}
+
+// Split on `separator` outside of links and `{{{ }}}`.
+fn split_top<'a>(text: &'a str, separator: &str) -> Vec<&'a str> {
+    let mut result = Vec::new();
+    let mut start = 0;
+    let mut i = 0;
+
+    while i < text.len() {
+        let rest = &text[i..];
+
+        let skip = if let Some(escaped) = rest.strip_prefix('\\') {
+            1 + escaped.chars().next().map_or(0, |c| c.len_utf8())
+        } else if let Some(link) = rest.strip_prefix("[[") {
+            find_closing(link, "[[", "]]").map_or(2, |x| x + 4)
+        } else if let Some(literal) = rest.strip_prefix("{{{") {
+            find_closing(literal, "{{{", "}}}").map_or(3, |x| x + 6)
+        } else if rest.starts_with(separator) {
+            result.push(&text[start..i]);
+            start = i + separator.len();
+            separator.len()
+        } else {
+            rest.chars().next().unwrap().len_utf8()
+        };
+
+        i += skip;
+    }
+
+    result.push(&text[start..]);
+    result
+}
+
+fn find_unescaped(text: &str, target: char) -> Option<usize> {
+    let mut escaped = false;
+
+    for (i, c) in text.char_indices() {
+        if escaped {
+            escaped = false;
+        } else if c == '\\' {
+            escaped = true;
+        } else if c == target {
+            return Some(i);
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Block, Document, Inline, ListItem, Style, TableCell};
+
+    #[test]
+    fn namumark_block_test() {
+        let text = "#redirect 동방 프로젝트";
+        assert_eq!(
+            Document::parse(text).blocks,
+            vec![Block::Redirect("동방 프로젝트")]
+        );
+
+        let text = "#REDIRECT  동방 프로젝트#s-2\n[[분류:동방]]";
+        assert_eq!(
+            Document::parse(text).blocks[0],
+            Block::Redirect("동방 프로젝트")
+        );
+
+        let text = "[목차]\n== 개요 ==\n첫 줄\n둘째 줄\n\n==# 역사 #==\n----\n * 하나\n  1. 둘\n> 인용\n## 주석";
+        let blocks = Document::parse(text).blocks;
+
+        assert_eq!(
+            blocks,
+            vec![
+                Block::Paragraph(vec![Inline::Macro {
+                    name: "목차",
+                    args: None
+                }]),
+                Block::Heading {
+                    level: 2,
+                    folded: false,
+                    title: vec![Inline::Text("개요")],
+                },
+                Block::Paragraph(vec![
+                    Inline::Text("첫 줄"),
+                    Inline::LineBreak,
+                    Inline::Text("둘째 줄")
+                ]),
+                Block::Heading {
+                    level: 2,
+                    folded: true,
+                    title: vec![Inline::Text("역사")],
+                },
+                Block::HorizontalRule,
+                Block::List(vec![
+                    ListItem {
+                        depth: 1,
+                        marker: "*",
+                        content: vec![Inline::Text("하나")],
+                    },
+                    ListItem {
+                        depth: 2,
+                        marker: "1.",
+                        content: vec![Inline::Text("둘")],
+                    },
+                ]),
+                Block::Quote(vec![Inline::Text("인용")]),
+                Block::Comment(" 주석"),
+            ]
+        );
+    }
+
+    #[test]
+    fn namumark_brace_block_test() {
+        let text = "{{{#!folding [ 펼치기 ]\n=== 목록 ===\n{{{#!syntax rust\nfn main() {}\n}}}\n}}}\n{{{#!wiki style=\"color: red\"\n본문}}}\n{{{#!html <b>굵게</b>}}}";
+        let blocks = Document::parse(text).blocks;
+
+        assert_eq!(
+            blocks,
+            vec![
+                Block::Folding {
+                    summary: "[ 펼치기 ]",
+                    blocks: vec![
+                        Block::Heading {
+                            level: 3,
+                            folded: false,
+                            title: vec![Inline::Text("목록")],
+                        },
+                        Block::Code {
+                            language: Some("rust"),
+                            text: "fn main() {}\n",
+                        },
+                    ],
+                },
+                Block::Wiki {
+                    attributes: "style=\"color: red\"",
+                    blocks: vec![Block::Paragraph(vec![Inline::Text("본문")])],
+                },
+                Block::Html("<b>굵게</b>"),
+            ]
+        );
+    }
+
+    #[test]
+    fn namumark_table_test() {
+        let text =
+            "||<-2><bgcolor=#fff> 제목 ||\n|| [[하쿠레이 신사|신사]] || 둘\n줄 ||\n||||<:> 병합 ||";
+        let blocks = Document::parse(text).blocks;
+
+        assert_eq!(
+            blocks,
+            vec![Block::Table(vec![
+                vec![TableCell {
+                    span: 1,
+                    attributes: vec!["-2", "bgcolor=#fff"],
+                    content: vec![Inline::Text("제목")],
+                }],
+                vec![
+                    TableCell {
+                        span: 1,
+                        attributes: vec![],
+                        content: vec![Inline::Link {
+                            target: "하쿠레이 신사",
+                            anchor: None,
+                            label: Some(vec![Inline::Text("신사")]),
+                        }],
+                    },
+                    TableCell {
+                        span: 1,
+                        attributes: vec![],
+                        content: vec![Inline::Text("둘"), Inline::LineBreak, Inline::Text("줄")],
+                    },
+                ],
+                vec![TableCell {
+                    span: 2,
+                    attributes: vec![":"],
+                    content: vec![Inline::Text("병합")],
+                }],
+            ])]
+        );
+    }
+
+    #[test]
+    fn namumark_inline_test() {
+        let blocks = Document::parse(
+            "'''굵게''' ''기울임'' ~~취소~~ {{{#red 빨강}}} {{{+1 크게}}} {{{[[문자]]}}} \\'''이스케이프",
+        )
+        .blocks;
+
+        assert_eq!(
+            blocks,
+            vec![Block::Paragraph(vec![
+                Inline::Styled {
+                    style: Style::Bold,
+                    children: vec![Inline::Text("굵게")]
+                },
+                Inline::Text(" "),
+                Inline::Styled {
+                    style: Style::Italic,
+                    children: vec![Inline::Text("기울임")]
+                },
+                Inline::Text(" "),
+                Inline::Styled {
+                    style: Style::Strike,
+                    children: vec![Inline::Text("취소")]
+                },
+                Inline::Text(" "),
+                Inline::Styled {
+                    style: Style::Color("#red"),
+                    children: vec![Inline::Text("빨강")]
+                },
+                Inline::Text(" "),
+                Inline::Styled {
+                    style: Style::Size(1),
+                    children: vec![Inline::Text("크게")]
+                },
+                Inline::Text(" "),
+                Inline::Literal("[[문자]]"),
+                Inline::Text(" "),
+                Inline::Text("'"),
+                Inline::Text("''이스케이프"),
+            ])]
+        );
+
+        // not a color, the second part starts with a multibyte character
+        assert_eq!(
+            Document::parse("{{{#red,가나 글자}}}").blocks,
+            vec![Block::Paragraph(vec![Inline::Literal("#red,가나 글자")])]
+        );
+
+        let blocks = Document::parse(
+            "[[동방 프로젝트#s-1]][[분류:동방 프로젝트#blur]][[파일:a.png|width=100]][include(틀:동방, 색=red)][*A 각주 [[링크]]][youtube(abc)]",
+        )
+        .blocks;
+
+        assert_eq!(
+            blocks,
+            vec![Block::Paragraph(vec![
+                Inline::Link {
+                    target: "동방 프로젝트",
+                    anchor: Some("s-1"),
+                    label: None
+                },
+                Inline::Category("동방 프로젝트"),
+                Inline::File {
+                    name: "a.png",
+                    options: Some("width=100")
+                },
+                Inline::Include {
+                    target: "틀:동방",
+                    params: vec![("색", "red")]
+                },
+                Inline::Footnote {
+                    name: Some("A"),
+                    children: vec![
+                        Inline::Text("각주 "),
+                        Inline::Link {
+                            target: "링크",
+                            anchor: None,
+                            label: None
+                        }
+                    ]
+                },
+                Inline::Macro {
+                    name: "youtube",
+                    args: Some("abc")
+                },
+            ])]
+        );
+
+        // unclosed markup is text
+        assert_eq!(
+            Document::parse("[[열린 링크 '''굵게").blocks,
+            vec![Block::Paragraph(vec![Inline::Text("[[열린 링크 '''굵게")])]
+        );
+    }
+
+    #[test]
+    fn namumark_extract_test() {
+        let text = "[include(틀:동방 프로젝트)]\n== [[개요]] ==\n[[하쿠레이 레이무|레이무]]는 [[하쿠레이 신사]]의 무녀.[* [[박려신사]]]\n||[[안개 호수]]||\n{{{#!folding 더 보기\n * [[홍마관]]}}}\n[[분류:동방 프로젝트]]";
+        let document = Document::parse(text);
+
+        assert_eq!(
+            document.links(),
+            vec![
+                "개요",
+                "하쿠레이 레이무",
+                "하쿠레이 신사",
+                "박려신사",
+                "안개 호수",
+                "홍마관"
+            ]
+        );
+        assert_eq!(document.categories(), vec!["동방 프로젝트"]);
+        assert_eq!(document.includes(), vec!["틀:동방 프로젝트"]);
+        assert_eq!(
+            document.to_plain_text(),
+            "개요\n레이무는 하쿠레이 신사의 무녀.\n안개 호수\n더 보기\n홍마관\n박려신사\n"
+        );
+    }
+}
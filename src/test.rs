@@ -1,12 +1,16 @@
 #[cfg(test)]
 mod tests {
     use loader::load::load_dump;
-    use model::{article::Article, article_category::ArticleCategory};
     use std::{collections::HashMap, fs};
 
-    use regex::Regex;
-
-    use crate::{loader, model};
+    use crate::{
+        core::{
+            ir::IRBuilder,
+            vm::{RuntimeRef, VirtualMachine},
+        },
+        index::{category::CategoryIndex, title::TitleIndex},
+        loader, vm_from, DEFAULT_CATEGORY_INDEX_PATH, DEFAULT_TITLE_INDEX_PATH,
+    };
 
     const DUMP_PATH: &str = "namuwiki_20210301.json";
 
@@ -56,41 +60,49 @@ mod tests {
         fs::write("r.json", format!("{:#?}", v)).unwrap();
     }
 
-    #[test]
-    fn find_by_category_test() {
-        let what = "하쿠레이 신사";
-        let js = load_dump(DUMP_PATH).unwrap();
+    // Titles of the articles `target` returns over the dump.
+    fn query(target: &str) -> Vec<String> {
+        let irb = IRBuilder::from(target).unwrap();
 
-        let mut r: Vec<(&str, Vec<&str>)> = Vec::new();
+        let head_inst = irb.build();
+        let insts = IRBuilder::ir_flatten(&head_inst);
 
-        for x in &js {
-            let category = x.categories();
+        let vm = vm_from!(insts);
 
-            if category.iter().any(|x| x.starts_with(what)) {
-                r.push((&x.title, category));
-            }
-        }
+        let tindex = TitleIndex::load(DUMP_PATH, DEFAULT_TITLE_INDEX_PATH).unwrap();
+        let cindex = CategoryIndex::load(DEFAULT_CATEGORY_INDEX_PATH).unwrap();
 
-        r.sort_by(|a, b| a.0.cmp(b.0));
+        let rt_ref = RuntimeRef {
+            category_index: &cindex,
+            category_tree: None,
+            title_index: &tindex,
+            body_index: None,
+            link_index: None,
+            rank_index: None,
+            articles: None,
+        };
 
-        fs::write("t.json", format!("{:#?}", r)).unwrap();
+        vm.run(&rt_ref)
+            .unwrap()
+            .data
+            .unwrap_array()
+            .iter()
+            .map(|x| x.unwrap_primitive().unwrap_article().title.clone())
+            .collect()
     }
 
     #[test]
-    fn find_by_category<'a>() {
-        let js = load_dump(DUMP_PATH).unwrap();
-        let what = "일본 애니메이션/";
-        let mut result: Vec<String> = Vec::new();
+    fn find_by_category_test() {
+        let r = query("category:startswith(\"하쿠레이 신사\")");
 
-        for x in js {
-            let category = x.categories();
+        fs::write("t.json", format!("{:#?}", r)).unwrap();
+    }
 
-            if category.iter().any(|x| x.starts_with(what)) {
-                result.push(x.title);
-            }
-        }
+    #[test]
+    fn find_by_category() {
+        let result = query("category:startswith(\"일본 애니메이션/\")");
 
-        result.sort_by(|a, b| a.cmp(b));
+        assert!(result.windows(2).all(|x| x[0] < x[1]));
     }
 
     #[test]
@@ -126,45 +138,6 @@ mod tests {
 
     #[test]
     fn extract_category() {
-        let js = load_dump(DUMP_PATH).unwrap();
-        let article_categories = js
-            .iter()
-            .map(|x| x.to_article_category())
-            .collect::<Vec<ArticleCategory>>();
-
-        let json_result = serde_json::to_string_pretty(&article_categories).unwrap();
-        fs::write("article-with-categories.json", json_result).unwrap();
-    }
-
-    #[test]
-    fn create_title_index() {
-        let fs = fs::read_to_string("namuwiki_20210301.json").unwrap();
-        let re = Regex::new("\"title\":\"(.*?)\"").unwrap();
-
-        let mut result: HashMap<&str, Vec<usize>> = HashMap::new();
-
-        let mut last_key = "";
-
-        let front_len = "{\"namespace\":.,\"title\":\"".len();
-
-        for cap in re.captures_iter(&fs) {
-            let title_range = cap.get(1).unwrap().range();
-            let current_item_start = title_range.start - front_len;
-
-            if last_key != "" {
-                result
-                    .get_mut(last_key)
-                    .map(|val| val.push(current_item_start - 2));
-            }
-
-            last_key = cap.get(1).unwrap().as_str();
-
-            result.insert(cap.get(1).unwrap().as_str(), vec![current_item_start]);
-        }
-
-        result.get_mut(last_key).map(|val| val.push(fs.len() - 1));
-
-        let json_result = serde_json::to_string_pretty(&result).unwrap();
-        fs::write("title-index.json", json_result).unwrap();
+        CategoryIndex::build(DUMP_PATH, "article-with-categories.json").unwrap();
     }
 }